@@ -0,0 +1,161 @@
+//! Adaptive difficulty. Instead of one fixed difficulty, the AIs play at a level somewhere between Easy (0) and Hard
+//! (2), and on each turn pick one of the two neighbouring difficulties by how far the level is between them. After
+//! every game the level moves towards a win rate of about 45% for the people at the table, and during a game it
+//! leans on how far ahead or behind they are in cards.
+//!
+//! Everything it goes by is public: who won and how many cards everyone holds. The AIs never see more than their own
+//! hand.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+
+use crate::game::{GameEvent, GameObserver, Table};
+use crate::player::AIDifficulty;
+use crate::save::Seat;
+
+const TARGET: f64 = 0.45;
+/// How many recent games the win rate is taken over.
+const WINDOW: usize = 10;
+/// How far the level moves after a game, per unit of win rate off target.
+const GAIN: f64 = 1.0;
+/// How far the level leans, per card the people are ahead by, and at most.
+const LEAD_GAIN: f64 = 0.1;
+const MAX_LEAN: f64 = 0.5;
+const HARDEST: f64 = 2.0;
+
+struct State {
+    /// The level between games, 0.0 to [`HARDEST`].
+    base: f64,
+    /// Whether one of the people won, most recent last.
+    results: VecDeque<bool>,
+    /// How many cards the people are ahead by this game, smoothed over the last few turns.
+    lead: f64,
+}
+
+/// The shared level; every AI holds a handle to it, and [`Adjuster`] moves it.
+#[derive(Clone)]
+pub struct Adaptive(Arc<Mutex<State>>);
+
+impl Adaptive {
+    pub fn new(start: AIDifficulty) -> Adaptive {
+        let base = match start {
+            AIDifficulty::Easy => 0.0,
+            AIDifficulty::Medium => 1.0,
+            AIDifficulty::Hard => HARDEST,
+        };
+
+        Adaptive(Arc::new(Mutex::new(State { base, results: VecDeque::new(), lead: 0.0 })))
+    }
+
+    /// The level right now, including the lean from this game's hand sizes.
+    pub fn level(&self) -> f64 {
+        let state = self.0.lock().unwrap();
+        (state.base + (state.lead * LEAD_GAIN).clamp(-MAX_LEAN, MAX_LEAN)).clamp(0.0, HARDEST)
+    }
+
+    /// The difficulty to play this turn at.
+    pub fn pick(&self, rng: &mut impl Rng) -> AIDifficulty {
+        let level = self.level();
+        let lower = level.floor();
+        let step = if rng.gen_bool(level - lower) { lower + 1.0 } else { lower };
+
+        match step as u8 {
+            0 => AIDifficulty::Easy,
+            1 => AIDifficulty::Medium,
+            _ => AIDifficulty::Hard,
+        }
+    }
+
+    /// The fixed difficulty closest to the level between games, for ratings and the match history.
+    pub fn nearest(&self) -> AIDifficulty {
+        match self.0.lock().unwrap().base.round() as u8 {
+            0 => AIDifficulty::Easy,
+            1 => AIDifficulty::Medium,
+            _ => AIDifficulty::Hard,
+        }
+    }
+
+    /// The people's win rate over the recent games, if there have been any.
+    pub fn win_rate(&self) -> Option<f64> {
+        let state = self.0.lock().unwrap();
+        (!state.results.is_empty()).then(|| state.results.iter().filter(|won| **won).count() as f64 / state.results.len() as f64)
+    }
+
+    /// Describes where the AIs stand, e.g. "between Medium and Hard, closer to Hard".
+    pub fn describe(&self) -> String {
+        let base = self.0.lock().unwrap().base;
+        let names = ["Easy", "Medium", "Hard"];
+        let lower = base.floor() as usize;
+
+        match base - base.floor() {
+            f if f < 0.1 => names[lower].to_string(),
+            f if f > 0.9 => names[lower + 1].to_string(),
+            f => format!("between {} and {}, closer to {}", names[lower], names[lower + 1], names[lower + (f >= 0.5) as usize]),
+        }
+    }
+}
+
+/// Watches a game for [`Adaptive`]: the hand sizes as it goes, and who won at the end.
+pub struct Adjuster {
+    adaptive: Adaptive,
+    people: Vec<usize>,
+}
+
+impl Adjuster {
+    pub fn new(adaptive: Adaptive, seats: &[Seat]) -> Adjuster {
+        Adjuster {
+            adaptive,
+            people: seats.iter().enumerate().filter(|(_, s)| s.human).map(|(i, _)| i).collect(),
+        }
+    }
+
+    /// How many cards fewer the people hold than the AIs, on average.
+    fn lead(&self, table: &Table) -> f64 {
+        let average = |people: bool| {
+            let sizes = table.hands
+                .iter()
+                .enumerate()
+                .filter(|(seat, _)| self.people.contains(seat) == people)
+                .map(|(_, hand)| hand.len() as f64)
+                .collect::<Vec<f64>>();
+
+            sizes.iter().sum::<f64>() / sizes.len().max(1) as f64
+        };
+
+        average(false) - average(true)
+    }
+}
+
+impl GameObserver for Adjuster {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        if self.people.is_empty() {
+            return;
+        }
+
+        match event {
+            GameEvent::Started { .. } => self.adaptive.0.lock().unwrap().lead = 0.0,
+            GameEvent::TurnStarted { .. } => {
+                let lead = self.lead(table);
+                let mut state = self.adaptive.0.lock().unwrap();
+                state.lead += (lead - state.lead) * 0.2;
+            }
+            GameEvent::Won { player } => {
+                let mut state = self.adaptive.0.lock().unwrap();
+
+                state.results.push_back(self.people.contains(player));
+
+                if state.results.len() > WINDOW {
+                    state.results.pop_front();
+                }
+
+                let rate = state.results.iter().filter(|won| **won).count() as f64 / state.results.len() as f64;
+
+                state.base = (state.base + GAIN * (rate - TARGET)).clamp(0.0, HARDEST);
+                state.lead = 0.0;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::card::Card;
+use crate::theme;
+use crate::theme::Theme;
+
+/// Card faces are this many columns wide and `HEIGHT` rows tall.
+const WIDTH: usize = 7;
+const HEIGHT: usize = 5;
+/// How much of a card stays visible when the next one in the hand is laid over it.
+const OVERLAP: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Unicode,
+    /// Plain ASCII, for terminals and fonts without box-drawing characters.
+    Ascii,
+}
+
+impl Charset {
+    /// Falls back to ASCII when the locale or terminal says it can't show Unicode.
+    pub fn detect() -> Charset {
+        if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+            return Charset::Ascii;
+        }
+
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+
+        match locale {
+            Some(locale) if !locale.to_lowercase().replace('-', "").contains("utf8") => Charset::Ascii,
+            _ => Charset::Unicode,
+        }
+    }
+
+    fn border(&self) -> [char; 6] {
+        match self {
+            Charset::Unicode => ['┌', '┐', '└', '┘', '─', '│'],
+            Charset::Ascii => ['+', '+', '+', '+', '-', '|'],
+        }
+    }
+}
+
+fn symbol(card: Card, charset: Charset) -> String {
+    match (card, charset) {
+        (Card::Numeric { value, .. }, _) => value.number().to_string(),
+        (Card::Skip { .. }, Charset::Unicode) => "⊘".to_string(),
+        (Card::Skip { .. }, Charset::Ascii) => "S".to_string(),
+        (Card::Reverse { .. }, Charset::Unicode) => "⇄".to_string(),
+        (Card::Reverse { .. }, Charset::Ascii) => "R".to_string(),
+        (Card::DrawTwo { .. }, _) => "+2".to_string(),
+        (Card::Wild { .. }, _) => "W".to_string(),
+        (Card::DrawFour { .. }, _) => "+4".to_string(),
+    }
+}
+
+/// The rows of an uncolored card face. When color alone can't be relied on, the left edge also carries the color's glyph and letter,
+/// so they stay visible when the card is partly covered.
+fn face(card: Card, charset: Charset, theme: &Theme) -> [String; HEIGHT] {
+    let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = charset.border();
+    let symbol = symbol(card, charset);
+    let inner = WIDTH - 2;
+    let edge = horizontal.to_string().repeat(inner);
+
+    let (glyph, letter) = match card.color() {
+        Some(color) if theme.needs_markers() => {
+            let mut marker = theme.marker(color).chars();
+            let glyph = marker.next().unwrap();
+            let letter = marker.next().unwrap();
+
+            (if charset == Charset::Ascii { letter } else { glyph }, letter)
+        }
+        _ => (' ', ' '),
+    };
+
+    let rest = inner - 1;
+
+    [
+        format!("{top_left}{edge}{top_right}"),
+        format!("{vertical}{symbol:<inner$}{vertical}"),
+        format!("{vertical}{glyph}{symbol:^rest$}{vertical}"),
+        format!("{vertical}{letter}{symbol:>rest$}{vertical}"),
+        format!("{bottom_left}{edge}{bottom_right}"),
+    ]
+}
+
+/// A single card face, one line per row.
+pub fn render_card(card: Card, charset: Charset) -> String {
+    let theme = theme::current();
+
+    face(card, charset, &theme)
+        .iter()
+        .map(|row| theme.paint(row, card.color()))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Lays `cards` out left to right, each one partly covering the one before, wrapping onto new rows of cards so no
+/// line is wider than `width` columns. Each card is numbered underneath, counting from 1, with a `*` after the number
+/// if it's one of `playable`; the others are dimmed.
+pub fn render_hand(cards: &[Card], playable: &[Card], width: usize, charset: Charset) -> String {
+    let theme = theme::current();
+    let per_row = (width.saturating_sub(WIDTH) / OVERLAP + 1).max(1);
+
+    cards
+        .chunks(per_row)
+        .enumerate()
+        .map(|(row_index, row)| {
+            let faces = row.iter().map(|card| face(*card, charset, &theme)).collect::<Vec<_>>();
+            let visible = |i: usize| if i + 1 == row.len() { WIDTH } else { OVERLAP };
+
+            let mut lines = (0..HEIGHT)
+                .map(|line| {
+                    faces
+                        .iter()
+                        .zip(row.iter())
+                        .enumerate()
+                        .map(|(i, (face, card))| {
+                            let text = face[line].chars().take(visible(i)).collect::<String>();
+
+                            if playable.contains(card) {
+                                theme.paint(&text, card.color())
+                            } else {
+                                theme.dim(&theme.paint(&text, card.color()))
+                            }
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>();
+
+            let numbers = row
+                .iter()
+                .enumerate()
+                .map(|(i, card)| {
+                    let number = row_index * per_row + i + 1;
+                    let label = if playable.contains(card) { format!("{number}*") } else { number.to_string() };
+
+                    format!("{label:<width$}", width = visible(i))
+                })
+                .collect::<String>();
+
+            lines.push(numbers.trim_end().to_string());
+            lines.join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Columns available for drawing, or a conservative guess when that can't be determined.
+pub fn terminal_width() -> usize {
+    crossterm::terminal::size().ok().filter(|(width, _)| *width > 0).map_or(80, |(width, _)| width as usize)
+}
@@ -87,8 +87,8 @@ impl Deck {
         deck
     }
 
-    pub fn shuffle(&mut self) {
-        self.cards.shuffle(&mut rand::thread_rng());
+    pub fn shuffle(&mut self, rng: &mut impl Rng) {
+        self.cards.shuffle(rng);
     }
 
     pub fn draw(&mut self) -> Option<Card> {
@@ -107,8 +107,7 @@ impl Deck {
         cards
     }
 
-    pub fn reinsert_random(&mut self, card: Card) {
-        let mut rng = rand::thread_rng();
+    pub fn reinsert_random(&mut self, card: Card, rng: &mut impl Rng) {
         let index = rng.gen_range(0..self.cards.len());
 
         self.cards.insert(index, card);
@@ -131,11 +130,11 @@ impl FromStr for CardColor {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "red" => Ok(CardColor::Red),
-            "green" => Ok(CardColor::Green),
-            "blue" => Ok(CardColor::Blue),
-            "yellow" => Ok(CardColor::Yellow),
-            _ => Err(format!("{s} is not a valid color")),
+            "r" | "red" => Ok(CardColor::Red),
+            "g" | "green" => Ok(CardColor::Green),
+            "b" | "blue" => Ok(CardColor::Blue),
+            "y" | "yellow" => Ok(CardColor::Yellow),
+            _ => Err(format!("{s} is not a valid color; use red, green, blue or yellow, or just r, g, b or y")),
         }
     }
 }
@@ -157,8 +156,12 @@ impl Display for CardValue {
     }
 }
 
+/// The alternate form, `{:#}`, gives the compact notation that [`Card::from_str`] reads.
 impl Display for Card {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return f.write_str(&self.notation());
+        }
 
         match self {
             Card::Numeric { color, value } => write!(f, "{color} {value}"),
@@ -173,6 +176,94 @@ impl Display for Card {
     }
 }
 
+/// Compact notation: a color letter followed by the value, e.g. `R5`, `GS` (skip), `BR` (reverse), `Y+2`. Wild cards
+/// are `W` and `+4`, with the chosen color after a colon once there is one: `W:R`, `+4:B`. Parsing ignores case.
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_uppercase();
+
+        let (card, chosen) = match upper.split_once(':') {
+            Some((card, color)) => (card, Some(color)),
+            None => (upper.as_str(), None),
+        };
+
+        let wild_color = |color: Option<&str>| color.map(CardColor::from_str).transpose();
+
+        match card {
+            "W" => return Ok(Card::Wild { color: wild_color(chosen)? }),
+            "+4" => return Ok(Card::DrawFour { color: wild_color(chosen)? }),
+            _ if chosen.is_some() => return Err(format!("{s} is not a valid card; only W and +4 take a chosen color")),
+            _ => {}
+        }
+
+        let mut chars = card.chars();
+        let color = chars.next().map(|c| CardColor::from_str(&c.to_string())).transpose()?;
+        let color = color.ok_or_else(|| "an empty string is not a valid card".to_string())?;
+
+        let value = match chars.as_str() {
+            "S" => return Ok(Card::Skip { color }),
+            "R" => return Ok(Card::Reverse { color }),
+            "+2" => return Ok(Card::DrawTwo { color }),
+            // Exactly one digit: `u8::from_str` would also take "05" and "+5".
+            value => match value.as_bytes() {
+                [digit @ b'0'..=b'9'] => CardValue::from_number(digit - b'0'),
+                _ => None,
+            },
+        };
+
+        match value {
+            Some(value) => Ok(Card::Numeric { color, value }),
+            None => Err(format!("{s} is not a valid card")),
+        }
+    }
+}
+
+impl CardColor {
+    pub fn letter(&self) -> char {
+        match self {
+            CardColor::Red => 'R',
+            CardColor::Green => 'G',
+            CardColor::Blue => 'B',
+            CardColor::Yellow => 'Y',
+        }
+    }
+}
+
+impl CardValue {
+    pub fn from_number(number: u8) -> Option<CardValue> {
+        match number {
+            0 => Some(CardValue::Zero),
+            1 => Some(CardValue::One),
+            2 => Some(CardValue::Two),
+            3 => Some(CardValue::Three),
+            4 => Some(CardValue::Four),
+            5 => Some(CardValue::Five),
+            6 => Some(CardValue::Six),
+            7 => Some(CardValue::Seven),
+            8 => Some(CardValue::Eight),
+            9 => Some(CardValue::Nine),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            CardValue::Zero => 0,
+            CardValue::One => 1,
+            CardValue::Two => 2,
+            CardValue::Three => 3,
+            CardValue::Four => 4,
+            CardValue::Five => 5,
+            CardValue::Six => 6,
+            CardValue::Seven => 7,
+            CardValue::Eight => 8,
+            CardValue::Nine => 9,
+        }
+    }
+}
+
 impl PartialEq for Card {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -190,6 +281,31 @@ impl PartialEq for Card {
 }
 
 impl Card {
+    /// What the card is worth to whoever goes out while it's still in someone's hand: its number, 20 for the other
+    /// colored cards and 50 for wilds.
+    #[cfg_attr(not(feature = "history"), allow(dead_code))]
+    pub fn points(&self) -> u32 {
+        match self {
+            Card::Numeric { value, .. } => value.number() as u32,
+            Card::Skip { .. } | Card::Reverse { .. } | Card::DrawTwo { .. } => 20,
+            Card::Wild { .. } | Card::DrawFour { .. } => 50,
+        }
+    }
+
+    /// The card in the compact notation parsed by `Card::from_str`.
+    pub fn notation(&self) -> String {
+        match self {
+            Card::Numeric { color, value } => format!("{}{}", color.letter(), value.number()),
+            Card::Skip { color } => format!("{}S", color.letter()),
+            Card::Reverse { color } => format!("{}R", color.letter()),
+            Card::DrawTwo { color } => format!("{}+2", color.letter()),
+            Card::Wild { color: None } => "W".to_string(),
+            Card::Wild { color: Some(color) } => format!("W:{}", color.letter()),
+            Card::DrawFour { color: None } => "+4".to_string(),
+            Card::DrawFour { color: Some(color) } => format!("+4:{}", color.letter()),
+        }
+    }
+
     pub fn color(&self) -> Option<CardColor> {
         match &self {
             Card::Numeric { color, .. } => Some(*color),
@@ -251,3 +367,47 @@ impl Card {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+
+    #[test]
+    fn notation_round_trips_every_card_in_the_deck() {
+        for card in Deck::generate().cards {
+            let notation = format!("{card:#}");
+
+            assert_eq!(notation.parse::<Card>(), Ok(card), "{notation}");
+            assert_eq!(notation.to_lowercase().parse::<Card>(), Ok(card), "{notation}");
+        }
+    }
+
+    #[test]
+    fn notation_round_trips_chosen_colors() {
+        for mut card in Deck::generate().cards.into_iter().filter(|c| c.color().is_none()) {
+            for color in COLORS {
+                let notation = format!("{:#}", card.with_color(color).unwrap());
+                let parsed = notation.parse::<Card>().unwrap();
+
+                // Equality ignores a wild's color, so compare that separately.
+                assert_eq!(parsed, card, "{notation}");
+                assert_eq!(parsed.color(), Some(color), "{notation}");
+            }
+        }
+    }
+
+    #[test]
+    fn plain_display_is_unchanged() {
+        assert_eq!(Card::Numeric { color: CardColor::Red, value: CardValue::Five }.to_string(), "Red Five");
+        assert_eq!(format!("{:#}", Card::Numeric { color: CardColor::Red, value: CardValue::Five }), "R5");
+    }
+
+    #[test]
+    fn numbers_are_a_single_digit() {
+        for notation in ["R05", "R+5", "R-5", "R10", "R 5", "R", "R٣"] {
+            assert!(notation.parse::<Card>().is_err(), "{notation} should not parse");
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use crate::player::AIDifficulty;
+use crate::theme::Palette;
+use crate::save;
+use crate::server;
+
+pub struct Options {
+    pub difficulty: Option<AIDifficulty>,
+    /// Pick the difficulty whose rating is closest to the players' own.
+    pub matchmaking: bool,
+    /// Let the AIs' difficulty follow how the players are doing.
+    pub adaptive: bool,
+    /// Personalities for the AI seats, in seating order.
+    pub personalities: Vec<String>,
+    /// A TOML file with more personalities.
+    pub personality_file: Option<PathBuf>,
+    pub serve: Option<String>,
+    pub humans: usize,
+    pub ai_players: Option<usize>,
+    pub spectate: bool,
+    pub delay: Option<u64>,
+    pub tui: bool,
+    pub ascii: bool,
+    pub palette: Option<Palette>,
+    pub colorblind: bool,
+    pub accessible: bool,
+    pub coach: bool,
+    /// Directory to write a record of every game to.
+    pub record: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub replay: Option<PathBuf>,
+    pub verify: Option<PathBuf>,
+    /// A saved game to continue instead of starting a new one.
+    pub load: Option<PathBuf>,
+    /// Show one profile's statistics, or everyone's if no name was given.
+    pub stats: Option<Option<String>>,
+    pub leaderboard: bool,
+    /// A match history query: the words after `history`.
+    pub history: Option<Vec<String>>,
+    /// Options for tuning a personality: the words after `tune`.
+    pub tune: Option<Vec<String>>,
+    /// Options for serving the learning environment: the words after `env`.
+    pub env: Option<Vec<String>>,
+}
+
+impl Options {
+    pub fn parse() -> Options {
+        let mut options = Options {
+            difficulty: None,
+            matchmaking: false,
+            adaptive: false,
+            personalities: vec![],
+            personality_file: None,
+            serve: None,
+            humans: 1,
+            ai_players: None,
+            spectate: false,
+            delay: None,
+            tui: false,
+            ascii: false,
+            palette: None,
+            colorblind: false,
+            accessible: false,
+            coach: false,
+            record: None,
+            seed: None,
+            replay: None,
+            verify: None,
+            load: None,
+            stats: None,
+            leaderboard: false,
+            history: None,
+            tune: None,
+            env: None,
+        };
+
+        let mut args = std::env::args().skip(1).peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.to_lowercase().as_str() {
+                "-e" | "--easy" => options.difficulty = Some(AIDifficulty::Easy),
+                "-m" | "--medium" => options.difficulty = Some(AIDifficulty::Medium),
+                "-h" | "--hard" => options.difficulty = Some(AIDifficulty::Hard),
+                "--match" => options.matchmaking = true,
+                "--adaptive" => options.adaptive = true,
+                "--personality" => match args.next() {
+                    Some(name) => options.personalities.push(name),
+                    None => println!("--personality needs a name."),
+                },
+                "--personalities" => match args.next() {
+                    Some(file) => options.personality_file = Some(PathBuf::from(file)),
+                    None => println!("--personalities needs a file."),
+                },
+                "--serve" => {
+                    let address = args.next_if(|a| !a.starts_with('-'));
+                    options.serve = Some(address.unwrap_or_else(|| server::DEFAULT_ADDRESS.to_string()));
+                }
+                "--humans" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(humans) => options.humans = humans,
+                    None => println!("--humans needs a number of players."),
+                },
+                "--ai" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(ai_players) => options.ai_players = Some(ai_players),
+                    None => println!("--ai needs a number of players."),
+                },
+                "--spectate" => options.spectate = true,
+                "--tui" => options.tui = true,
+                "--ascii" => options.ascii = true,
+                "--theme" => match args.next().map(|p| p.parse()) {
+                    Some(Ok(palette)) => options.palette = Some(palette),
+                    _ => println!("--theme needs one of 16, truecolor or mono."),
+                },
+                "--colorblind" => options.colorblind = true,
+                "--accessible" => options.accessible = true,
+                "--coach" => options.coach = true,
+                "--record" => match args.next() {
+                    Some(dir) => options.record = Some(PathBuf::from(dir)),
+                    None => println!("--record needs a directory to write games to."),
+                },
+                "--seed" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(seed) => options.seed = Some(seed),
+                    None => println!("--seed needs a number."),
+                },
+                "replay" | "--replay" => match args.next() {
+                    Some(file) => options.replay = Some(PathBuf::from(file)),
+                    None => println!("replay needs a record file."),
+                },
+                "verify" | "--verify" => match args.next() {
+                    Some(file) => options.verify = Some(PathBuf::from(file)),
+                    None => println!("verify needs a record file."),
+                },
+                "load" | "--load" => {
+                    let file = args.next_if(|a| !a.starts_with('-'));
+                    options.load = Some(file.map_or_else(save::default_path, PathBuf::from));
+                }
+                "stats" | "--stats" => options.stats = Some(args.next_if(|a| !a.starts_with('-'))),
+                "leaderboard" | "--leaderboard" => options.leaderboard = true,
+                // Everything after it belongs to the query.
+                "history" | "--history" => options.history = Some(args.by_ref().collect()),
+                "tune" | "--tune" => options.tune = Some(args.by_ref().collect()),
+                "env" | "--env" => options.env = Some(args.by_ref().collect()),
+                "--delay" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(delay) => options.delay = Some(delay),
+                    None => println!("--delay needs a number of milliseconds."),
+                },
+                _ => println!("Unknown option {arg}; ignoring it."),
+            }
+        }
+
+        options
+    }
+}
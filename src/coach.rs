@@ -0,0 +1,97 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::card::{Card, CardColor};
+use crate::game::{Turn, TurnResult};
+use crate::player::{AIDifficulty, Ai};
+
+/// A suggested move for the human, with the reason for it in a few words.
+pub struct Hint {
+    pub play: TurnResult,
+    pub rationale: String,
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.play {
+            TurnResult::Played(card) => write!(f, "Play {card}: {}.", self.rationale),
+            TurnResult::Drew => write!(f, "Draw: {}.", self.rationale),
+        }
+    }
+}
+
+/// Asks the strongest AI what it would do with `turn`.
+pub fn hint(turn: &Turn) -> Hint {
+    if turn.playable_hand.is_empty() {
+        let rationale = if turn.to_draw > 0 {
+            format!("you can't stack on the pending draw, so take the {} cards", turn.to_draw)
+        } else {
+            "nothing in your hand can be played".to_string()
+        };
+
+        return Hint { play: TurnResult::Drew, rationale };
+    }
+
+    let play = Ai::new(StdRng::from_entropy(), AIDifficulty::Hard).suggest(turn);
+
+    let rationale = match play {
+        TurnResult::Played(card) => rationale(turn, card),
+        TurnResult::Drew => "holding on to your cards is worth more than anything you could play".to_string(),
+    };
+
+    Hint { play, rationale }
+}
+
+fn plural(color: CardColor) -> String {
+    format!("{}s", color.to_string().to_lowercase())
+}
+
+fn rationale(turn: &Turn, card: Card) -> String {
+    let mut rest = turn.full_hand.clone();
+
+    if let Some(index) = rest.iter().position(|c| *c == card) {
+        rest.remove(index);
+    }
+
+    let next = turn.opponents.first().copied().unwrap_or(0);
+    let cards = |n: usize| if n == 1 { "1 card".to_string() } else { format!("{n} cards") };
+
+    if rest.is_empty() {
+        return "it's your last card".to_string();
+    }
+
+    if turn.to_draw > 0 {
+        return format!("passes the {} cards on instead of drawing them", turn.to_draw);
+    }
+
+    match card {
+        Card::Skip { .. } if next <= 2 => return format!("the next player has {}; Skip them", cards(next)),
+        Card::DrawTwo { .. } | Card::DrawFour { .. } if next <= 2 => {
+            return format!("the next player has {}; make them draw", cards(next))
+        }
+        // With only one opponent there's nobody to turn play towards instead.
+        Card::Reverse { .. } if next <= 2 && turn.opponents.len() > 1 => return format!("the next player has {}; turn play away from them", cards(next)),
+        _ => {}
+    }
+
+    let Some(color) = card.color() else {
+        return "it keeps your options open".to_string();
+    };
+
+    let left = rest.iter().filter(|c| c.color() == Some(color)).count();
+
+    let outcome = match left {
+        0 => format!("gets rid of your last {}", color.to_string().to_lowercase()),
+        1 => format!("leaves you with 1 {}", color.to_string().to_lowercase()),
+        left => format!("leaves you with {left} {}", plural(color)),
+    };
+
+    match card {
+        Card::Wild { .. } | Card::DrawFour { .. } => format!("switches to {color}, which {outcome}"),
+        _ if turn.last_card.color() == Some(color) => format!("keeps the color on {color} and {outcome}"),
+        _ => format!("switches to {color} and {outcome}"),
+    }
+}
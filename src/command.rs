@@ -0,0 +1,219 @@
+use std::str::FromStr;
+
+use crate::card::{Card, CardColor};
+use crate::hand::HandSort;
+
+pub const HELP: &str = "\
+Commands:
+  p 3          play the third card in your hand
+  p r5         play a card by name: r5, gs (skip), br (reverse), y+2
+  p w red      play a wild card and choose its color (or p w:r)
+  p wd4 b      play a wild draw four; colors can be single letters
+  d            draw
+  hand         show your hand again
+  sort value   order your hand by color, value or playable
+  why bs       explain whether a card can be played right now, and why
+  hint         ask the computer what it would play
+  coach        show a hint at the start of every turn, or stop showing them
+  history      show what happened recently
+  save         save the game; start with load to pick it up again
+  help         show this list
+  quit         leave the game";
+
+/// A card as the player referred to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardRef {
+    /// Position in the hand, counting from 1.
+    Index(usize),
+    Card(Card),
+}
+
+/// One line of input during a turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Play { card: CardRef, color: Option<CardColor> },
+    Draw,
+    Hand,
+    Sort(HandSort),
+    Why(CardRef),
+    Hint,
+    /// Turns hints at the start of every turn on or off.
+    Coach,
+    History,
+    /// Saves the game as it stood at the start of the turn.
+    Save,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().map(|w| w.to_lowercase()).collect::<Vec<String>>();
+        let Some((verb, args)) = words.split_first() else {
+            return Err("Type a command, or help to see them all.".to_string());
+        };
+
+        let no_args = |command: Command| if args.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("{verb} doesn't take anything after it."))
+        };
+
+        match verb.as_str() {
+            "p" | "play" => {
+                let (card, color) = match args {
+                    [] => return Err("Say which card to play, e.g. p 3 or p r5.".to_string()),
+                    [card] => (card, None),
+                    [card, color] => (card, Some(color)),
+                    _ => return Err("Too many words. Play a card with p <card> [color], e.g. p wd4 blue.".to_string()),
+                };
+
+                let card = parse_card_ref(card)?;
+                let color = color.map(|c| CardColor::from_str(c)).transpose()?;
+
+                Ok(Command::Play { card, color })
+            }
+            "d" | "draw" => no_args(Command::Draw),
+            "h" | "hand" => no_args(Command::Hand),
+            "hint" => no_args(Command::Hint),
+            "coach" => no_args(Command::Coach),
+            "why" => match args {
+                [card] => Ok(Command::Why(parse_card_ref(card)?)),
+                _ => Err("Say which card to ask about, e.g. why 3 or why bs.".to_string()),
+            },
+            "sort" => match args {
+                [order] => Ok(Command::Sort(order.parse()?)),
+                _ => Err("Say how to sort, e.g. sort color, sort value or sort playable.".to_string()),
+            },
+            "history" | "log" => no_args(Command::History),
+            "save" => no_args(Command::Save),
+            "?" | "help" => no_args(Command::Help),
+            "q" | "quit" | "exit" => no_args(Command::Quit),
+            _ => Err(format!("{verb} isn't a command. Type help to see them all.")),
+        }
+    }
+}
+
+fn parse_card_ref(s: &str) -> Result<CardRef, String> {
+    // `usize::from_str` takes a leading +, which would make "+4" card 4 rather than a draw four.
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(CardRef::Card(parse_card(s)?));
+    }
+
+    match s.parse::<usize>() {
+        Ok(0) => Err("Cards are numbered from 1.".to_string()),
+        Ok(index) => Ok(CardRef::Index(index)),
+        Err(_) => Err(format!("You don't have {s} cards.")),
+    }
+}
+
+/// Card notation (see `Card::from_str`), plus a few longer spellings such as `rskip`, `wd4` and `wild`.
+fn parse_card(s: &str) -> Result<Card, String> {
+    if let Ok(card) = s.parse::<Card>() {
+        return Ok(card);
+    }
+
+    match s {
+        "wild" => return Ok(Card::Wild { color: None }),
+        "wd4" | "w+4" | "d4" => return Ok(Card::DrawFour { color: None }),
+        _ => {}
+    }
+
+    let mut chars = s.chars();
+    let color = match chars.next() {
+        Some('r') => CardColor::Red,
+        Some('g') => CardColor::Green,
+        Some('b') => CardColor::Blue,
+        Some('y') => CardColor::Yellow,
+        _ => return Err(format!("{s} isn't a card. Start with a color letter (r, g, b or y), e.g. r5, or use w / wd4 for wilds.")),
+    };
+
+    match chars.as_str() {
+        "skip" => Ok(Card::Skip { color }),
+        "rev" | "reverse" => Ok(Card::Reverse { color }),
+        "d2" => Ok(Card::DrawTwo { color }),
+        "" => Err(format!("{s} is only a color. Add a number or s, r or +2, e.g. {s}5.")),
+        rest => Err(format!("{rest} isn't a card value. Use 0-9, s (skip), r (reverse) or +2.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardValue;
+
+    fn play(card: CardRef, color: Option<CardColor>) -> Command {
+        Command::Play { card, color }
+    }
+
+    #[test]
+    fn parses_commands() {
+        let red_five = Card::Numeric { color: CardColor::Red, value: CardValue::Five };
+
+        for (input, command) in [
+            ("p 3", play(CardRef::Index(3), None)),
+            ("  PLAY   12 ", play(CardRef::Index(12), None)),
+            ("p r5", play(CardRef::Card(red_five), None)),
+            ("p +4", play(CardRef::Card(Card::DrawFour { color: None }), None)),
+            ("p w red", play(CardRef::Card(Card::Wild { color: None }), Some(CardColor::Red))),
+            ("p 2 b", play(CardRef::Index(2), Some(CardColor::Blue))),
+            ("d", Command::Draw),
+            ("draw", Command::Draw),
+            ("hand", Command::Hand),
+            ("sort value", Command::Sort(HandSort::Value)),
+            ("why 1", Command::Why(CardRef::Index(1))),
+            ("why bs", Command::Why(CardRef::Card(Card::Skip { color: CardColor::Blue }))),
+            ("log", Command::History),
+            ("?", Command::Help),
+            ("exit", Command::Quit),
+        ] {
+            assert_eq!(input.parse::<Command>(), Ok(command), "{input}");
+        }
+    }
+
+    #[test]
+    fn parses_long_spellings() {
+        for (input, card) in [
+            ("wild", Card::Wild { color: None }),
+            ("wd4", Card::DrawFour { color: None }),
+            ("w+4", Card::DrawFour { color: None }),
+            ("d4", Card::DrawFour { color: None }),
+            ("rskip", Card::Skip { color: CardColor::Red }),
+            ("grev", Card::Reverse { color: CardColor::Green }),
+            ("breverse", Card::Reverse { color: CardColor::Blue }),
+            ("yd2", Card::DrawTwo { color: CardColor::Yellow }),
+        ] {
+            assert_eq!(format!("p {input}").parse::<Command>(), Ok(play(CardRef::Card(card), None)), "{input}");
+        }
+    }
+
+    #[test]
+    fn only_digits_are_an_index() {
+        // A leading + is a draw four, not a card number.
+        assert_eq!(parse_card_ref("+4"), Ok(CardRef::Card(Card::DrawFour { color: None })));
+        assert_eq!(parse_card_ref("4"), Ok(CardRef::Index(4)));
+
+        for input in ["-4", "4.0", " 4", "٤"] {
+            assert!(!matches!(parse_card_ref(input), Ok(CardRef::Index(_))), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        for (input, error) in [
+            ("", "Type a command, or help to see them all."),
+            ("p", "Say which card to play, e.g. p 3 or p r5."),
+            ("p 0", "Cards are numbered from 1."),
+            ("why 0", "Cards are numbered from 1."),
+            ("p 99999999999999999999999", "You don't have 99999999999999999999999 cards."),
+            ("p r", "r is only a color. Add a number or s, r or +2, e.g. r5."),
+            ("p w red now", "Too many words. Play a card with p <card> [color], e.g. p wd4 blue."),
+            ("d 2", "d doesn't take anything after it."),
+            ("fold", "fold isn't a command. Type help to see them all."),
+        ] {
+            assert_eq!(input.parse::<Command>(), Err(error.to_string()), "{input}");
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::stdin;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::card::Card;
+use crate::game::{Direction, GameEvent, GameObserver, GameState, Table};
+
+/// Prints the public side of the game to the terminal, as everyone at the table would see it.
+pub struct Transcript;
+
+impl GameObserver for Transcript {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        // The winner is announced by whoever started the game.
+        if !matches!(event, GameEvent::TurnStarted { .. } | GameEvent::Won { .. }) {
+            println!("{}", describe(event, table, GameState::get_colorized_card_name));
+        }
+    }
+}
+
+/// One line describing `event`, with cards written out by `card_name`.
+pub fn describe(event: &GameEvent, table: &Table, card_name: fn(Card) -> String) -> String {
+    let name = |seat: &usize| table.names.get(*seat).copied().unwrap_or("Someone");
+
+    match event {
+        GameEvent::Started { top_card, .. } => format!("The top card is: {}", card_name(*top_card)),
+        GameEvent::TurnStarted { player } => format!("It's {}'s turn", name(player)),
+        GameEvent::Played { player, card } => format!("{} played {}", name(player), card_name(*card)),
+        GameEvent::Drew { player, count } => format!("{} drew {count} card(s)", name(player)),
+        GameEvent::Skipped { player } => format!("{}'s turn was skipped", name(player)),
+        GameEvent::Won { player } => format!("{} went out!", name(player)),
+    }
+}
+
+/// Reads the game out as short plain sentences for screen readers: no escape codes, and the same order every turn.
+pub struct Announcer;
+
+impl GameObserver for Announcer {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let name = |seat: &usize| table.names.get(*seat).copied().unwrap_or("Someone");
+        let cards_left = |seat: &usize| table.hands.get(*seat).map_or(0, |hand| hand.len());
+
+        match event {
+            GameEvent::Started { players, top_card } => {
+                println!("New game. Seating order: {}.", players.join(", "));
+                println!("The top card is {top_card}.");
+            }
+            GameEvent::TurnStarted { player } => println!("{}'s turn.", name(player)),
+            GameEvent::Played { player, card } => println!("{} played {card}, and has {} cards left.", name(player), cards_left(player)),
+            GameEvent::Drew { player, count } => println!("{} drew {count}, and has {} cards.", name(player), cards_left(player)),
+            GameEvent::Skipped { player } => println!("{} is skipped.", name(player)),
+            // The winner is announced by whoever started the game.
+            GameEvent::Won { .. } => {}
+        }
+    }
+}
+
+/// Shows every hand after each turn and paces the game from the keyboard.
+pub struct Spectator {
+    commands: Receiver<String>,
+    delay: Duration,
+    paused: bool,
+    fast_forward: bool,
+}
+
+impl Spectator {
+    pub fn new(delay: Duration) -> Spectator {
+        let (sender, commands) = channel();
+
+        std::thread::spawn(move || {
+            let mut input = String::new();
+
+            while stdin().read_line(&mut input).is_ok_and(|read| read > 0) {
+                if sender.send(input.trim().to_lowercase()).is_err() {
+                    break;
+                }
+
+                input.clear();
+            }
+        });
+
+        println!("Press Enter to pause or resume, [S]tep while paused, [F]ast-forward, [+] faster, [-] slower, [Q]uit.");
+
+        Spectator {
+            commands,
+            delay,
+            paused: false,
+            fast_forward: false,
+        }
+    }
+
+    fn print_table(table: &Table) {
+        let arrow = match table.direction {
+            Direction::Clockwise => "↓",
+            Direction::CounterClockwise => "↑",
+        };
+
+        for (seat, (name, hand)) in table.names.iter().zip(table.hands.iter()).enumerate() {
+            let marker = if seat == table.current_player { arrow } else { " " };
+            let cards = hand.iter().map(|c| GameState::get_colorized_card_name(*c)).collect::<Vec<String>>();
+
+            println!(" {marker} {name} ({}): {}", hand.len(), cards.join(", "));
+        }
+    }
+
+    /// Waits out the delay between turns, handling any commands typed in the meantime.
+    fn pace(&mut self) {
+        let deadline = Instant::now() + if self.fast_forward { Duration::ZERO } else { self.delay };
+
+        loop {
+            let command = if self.paused {
+                self.commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                self.commands.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            };
+
+            let command = match command {
+                Ok(command) => command,
+                Err(RecvTimeoutError::Timeout) => return,
+                Err(RecvTimeoutError::Disconnected) => {
+                    // Nobody left to press resume.
+                    self.paused = false;
+                    return;
+                }
+            };
+
+            match command.as_str() {
+                "" | "p" | "pause" => {
+                    self.paused = !self.paused;
+                    println!("{}", if self.paused { "Paused." } else { "Resumed." });
+                }
+                "s" | "step" if self.paused => return,
+                "f" | "fast" => {
+                    self.fast_forward = !self.fast_forward;
+                    println!("Fast-forward {}.", if self.fast_forward { "on" } else { "off" });
+                }
+                "+" => {
+                    self.delay /= 2;
+                    println!("Delay is now {}ms.", self.delay.as_millis());
+                }
+                "-" => {
+                    self.delay = (self.delay * 2).max(Duration::from_millis(50));
+                    println!("Delay is now {}ms.", self.delay.as_millis());
+                }
+                "q" | "quit" => std::process::exit(0),
+                _ => println!("Unknown command {command}."),
+            }
+        }
+    }
+}
+
+impl GameObserver for Spectator {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        // The winner is announced by whoever started the game.
+        if matches!(event, GameEvent::TurnStarted { .. } | GameEvent::Won { .. }) {
+            return;
+        }
+
+        println!("{}", describe(event, table, GameState::get_colorized_card_name));
+
+        if matches!(event, GameEvent::Started { .. } | GameEvent::Played { .. } | GameEvent::Drew { .. }) {
+            Spectator::print_table(table);
+            self.pace();
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use crate::card::Card;
+use crate::game::{GameEvent, Turn, TurnResult};
+use crate::player::{AsyncPlayer, Player};
+
+/// What a seat does when its player runs out of time.
+pub enum Fallback {
+    Draw,
+    /// Let a stand-in decide the turn instead, usually an AI.
+    StandIn(Box<dyn Player>),
+}
+
+/// Adapts an [`AsyncPlayer`] to the engine, giving it `limit` to decide each turn before `fallback` takes over.
+pub struct Deadline<P: AsyncPlayer> {
+    player: P,
+    limit: Option<Duration>,
+    fallback: Fallback,
+}
+
+impl<P> Deadline<P> where P: AsyncPlayer {
+    pub fn new(player: P, limit: Option<Duration>, fallback: Fallback) -> Deadline<P> {
+        Deadline {
+            player,
+            limit,
+            fallback,
+        }
+    }
+}
+
+impl<P> Player for Deadline<P> where P: AsyncPlayer {
+    fn name(&self) -> &str {
+        self.player.name()
+    }
+
+    fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
+        self.player.start_turn(turn);
+
+        if let Some(result) = self.player.poll_turn(self.limit) {
+            return result;
+        }
+
+        self.player.cancel_turn();
+
+        match &mut self.fallback {
+            Fallback::Draw => TurnResult::Drew,
+            Fallback::StandIn(stand_in) => stand_in.execute_turn(turn),
+        }
+    }
+
+    fn observe_turn(&self, _other: &dyn Player, _card: &Card) {
+        // Nothing to do here.
+    }
+
+    fn observe_turn_skip(&mut self, observed_cards: Option<Vec<&Card>>) {
+        self.player.observe_turn_skip(observed_cards);
+    }
+
+    fn observe_event(&mut self, event: &GameEvent) {
+        self.player.observe_event(event);
+    }
+}
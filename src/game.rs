@@ -1,54 +1,201 @@
 
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
 use crate::card::{Card, CardColor, Deck};
 use crate::player::Player;
+use crate::rules::RuleSet;
+use crate::theme;
 
-pub struct GameState<'a> {
+pub struct GameState {
     deck: Deck,
     discard: Vec<Card>,
-    players: Vec<(&'a mut dyn Player, Vec<Card>)>,
+    players: Vec<(Box<dyn Player>, Vec<Card>)>,
+    observers: Vec<Box<dyn GameObserver>>,
     current_player: usize,
     direction: Direction,
     to_draw: u8,
+    rules: RuleSet,
+    turn_delay: Duration,
+    stop: Option<Stop>,
+    seed: u64,
+    rng: StdRng,
+    checkpoint: Option<Checkpoint>,
+}
+
+/// Watches a game without taking part in it, e.g. to print it or send it to spectators.
+pub trait GameObserver: Send {
+    fn observe(&mut self, event: &GameEvent, table: &Table);
 }
 
-pub struct Turn<'a> {
+/// Everything on the table, hands included, as of the event being observed. Observers decide how much of it to show.
+pub struct Table<'a> {
+    pub names: Vec<&'a str>,
+    pub hands: Vec<&'a [Card]>,
+    pub top_card: Option<Card>,
+    pub current_player: usize,
+    pub direction: Direction,
     pub to_draw: u8,
-    pub full_hand: &'a Vec<Card>,
-    pub playable_hand: &'a mut Vec<Card>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub to_draw: u8,
+    pub full_hand: Vec<Card>,
+    pub playable_hand: Vec<Card>,
     pub last_card: Card,
+    pub rules: RuleSet,
+    /// How many cards everyone else holds, in the order they play after this turn; the next player comes first.
+    pub opponents: Vec<usize>,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum TurnResult {
     Played(Card),
     Drew,
 }
 
+/// Public events broadcast to every seat as the game progresses. Players are referred to by seat index.
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    Started { players: Vec<String>, top_card: Card },
+    TurnStarted { player: usize },
+    Played { player: usize, card: Card },
+    Drew { player: usize, count: usize },
+    Skipped { player: usize },
+    Won { player: usize },
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Direction {
+pub enum Direction {
     Clockwise,
     CounterClockwise,
 }
 
+/// Where the game stood at the start of the current turn, or `None` once it's over. See [`GameState::set_checkpoint`].
+pub type Checkpoint = Arc<Mutex<Option<Position>>>;
+
+/// Set to end the game early, from outside or by one of its own seats or observers. See [`GameState::set_stop`].
+pub type Stop = Arc<AtomicBool>;
+
+/// A game in progress, to pick up from with [`GameState::from_position`].
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub deck: Vec<Card>,
+    /// The discard pile, top card last.
+    pub discard: Vec<Card>,
+    /// One hand per seat.
+    pub hands: Vec<Vec<Card>>,
+    /// The seat whose turn is next.
+    pub to_move: usize,
+    pub direction: Direction,
+    pub to_draw: u8,
+}
+
+
+impl GameState {
+    pub fn new(players: Vec<Box<dyn Player>>, rules: RuleSet) -> GameState {
+        let seed = rand::random();
 
-impl<'a> GameState<'a> {
-    pub fn new(players: Vec<&'a mut dyn Player>) -> GameState<'a> {
         GameState {
             deck: Deck::generate(),
             discard: vec![],
             players: players.into_iter().map(|p| (p, vec![])).collect(),
+            observers: vec![],
             current_player: 0,
             direction: Direction::Clockwise,
             to_draw: 0,
+            rules,
+            turn_delay: Duration::from_millis(800),
+            stop: None,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            checkpoint: None,
         }
     }
 
-    pub fn start(&mut self) -> String {
-        self.deck.shuffle();
+    /// Sets up a game that's already under way. `position` must have a hand for every player. Continue it with
+    /// [`GameState::play`].
+    pub fn from_position(players: Vec<Box<dyn Player>>, rules: RuleSet, position: Position) -> GameState {
+        let count = players.len();
+        let seed = rand::random();
+
+        // The turn loop advances before each turn, so start from whoever played last.
+        let current_player = match position.direction {
+            Direction::Clockwise => (position.to_move + count - 1) % count,
+            Direction::CounterClockwise => (position.to_move + 1) % count,
+        };
+
+        GameState {
+            deck: Deck { cards: position.deck },
+            discard: position.discard,
+            players: players.into_iter().zip(position.hands).collect(),
+            observers: vec![],
+            current_player,
+            direction: position.direction,
+            to_draw: position.to_draw,
+            rules,
+            turn_delay: Duration::from_millis(800),
+            stop: None,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            checkpoint: None,
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// How long to pause before each turn so people can follow along. Observers that pace the game themselves set this to zero.
+    pub fn set_turn_delay(&mut self, delay: Duration) {
+        self.turn_delay = delay;
+    }
+
+    /// Lets the game be called off by setting `stop`. It's checked before every turn and after every move, so the move
+    /// a player makes once it's set, e.g. because they've just set it, is never carried out. A stopped game has no
+    /// winner.
+    pub fn set_stop(&mut self, stop: Stop) {
+        self.stop = Some(stop);
+    }
+
+    fn stopped(stop: &Option<Stop>) -> bool {
+        stop.as_ref().is_some_and(|stop| stop.load(Ordering::SeqCst))
+    }
+
+    /// Every shuffle in the game follows from the seed, so the same seed and the same moves replay the same game.
+    /// Set it before [`GameState::start`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Keeps `checkpoint` up to date with the position at the start of every turn, so the game can be saved from
+    /// outside the turn loop, e.g. in the middle of someone's turn or when the program is interrupted.
+    pub fn set_checkpoint(&mut self, checkpoint: Checkpoint) {
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// Hands the seats back once the game is over, so they can be reused for another round.
+    pub fn into_players(self) -> Vec<Box<dyn Player>> {
+        self.players.into_iter().map(|(p, _)| p).collect()
+    }
+
+    pub fn start(&mut self) -> Option<String> {
+        self.deck.shuffle(&mut self.rng);
 
         for (_, hand) in self.players.iter_mut() {
 
-            let insert = self.deck.draw_multiple(7);
+            let insert = self.deck.draw_multiple(self.rules.hand_size);
             hand.extend(insert);
         }
 
@@ -57,69 +204,109 @@ impl<'a> GameState<'a> {
 
             match top_card {
                 Card::Wild { color: _ } => {
-                    self.deck.reinsert_random(top_card);
+                    self.deck.reinsert_random(top_card, &mut self.rng);
                 }
                 Card::DrawFour { color: _ } => {
-                    self.deck.reinsert_random(top_card);
+                    self.deck.reinsert_random(top_card, &mut self.rng);
                 }
                 Card::Skip { .. } => {
-                    self.deck.reinsert_random(top_card);
+                    self.deck.reinsert_random(top_card, &mut self.rng);
                 }
                 _ => {
                     self.discard.push(top_card);
-                    println!("The top card is: {}", Self::get_colorized_card_name(*self.discard.last().unwrap()));
+
+                    let players = self.players.iter().map(|(p, _)| p.name().to_string()).collect();
+                    self.broadcast(GameEvent::Started { players, top_card });
                     break;
                 }
             }
         }
 
+        self.play()
+    }
+
+    /// Starts a game set up with [`GameState::from_position`], announcing it like a new one so everyone watching
+    /// catches up with the table, and plays it to the end.
+    pub fn resume(&mut self) -> Option<String> {
+        let players = self.players.iter().map(|(p, _)| p.name().to_string()).collect();
+        let top_card = *self.discard.last().unwrap();
+
+        self.broadcast(GameEvent::Started { players, top_card });
+        self.play()
+    }
+
+    /// Plays turns until someone goes out, and returns their name, or `None` if the game is stopped first.
+    pub fn play(&mut self) -> Option<String> {
         loop {
-            std::thread::sleep(std::time::Duration::from_millis(800));
+            if Self::stopped(&self.stop) {
+                return None;
+            }
+
+            std::thread::sleep(self.turn_delay);
 
-            Self::ensure_drawable_deck(&mut self.deck, &mut self.discard, self.to_draw);
+            Self::ensure_drawable_deck(&mut self.deck, &mut self.discard, self.to_draw, &mut self.rng);
 
             self.current_player = self.next_player();
+            let seat = self.current_player;
+
+            if let Some(checkpoint) = &self.checkpoint {
+                *checkpoint.lock().unwrap() = Some(self.position());
+            }
+
+            self.broadcast(GameEvent::TurnStarted { player: seat });
+
+            let opponents = self.opponent_hand_sizes(seat);
 
             // Play for the current player
-            let (current_player, player_hand) = self.players.get_mut(self.current_player).unwrap();
+            let (current_player, player_hand) = self.players.get_mut(seat).unwrap();
 
-            let playable_player_hand = &mut Self::get_playable_hand(player_hand, self.discard.last().unwrap(), self.to_draw);
+            let playable_player_hand = Self::get_playable_hand(player_hand, self.discard.last().unwrap(), self.to_draw, &self.rules);
 
-            if self.to_draw > 0 && !Self::contains_special_card(playable_player_hand, self.discard.last().unwrap()) {
+            if self.to_draw > 0 && !Self::contains_special_card(&playable_player_hand, self.discard.last().unwrap()) {
                 let draw = &self.deck.draw_multiple(self.to_draw);
 
                 player_hand.extend(draw);
                 current_player.observe_turn_skip(Some(draw.iter().collect()));
 
-                println!("{} drew {} cards", current_player.name(), self.to_draw);
-
                 self.to_draw = 0;
+                self.broadcast(GameEvent::Drew { player: seat, count: draw.len() });
                 continue;
             }
 
             let turn = Turn {
-                full_hand: &player_hand.iter().copied().collect(),
+                full_hand: player_hand.clone(),
                 playable_hand: playable_player_hand,
                 to_draw: self.to_draw,
                 last_card: *self.discard.last().unwrap(),
+                rules: self.rules,
+                opponents,
             };
 
-            match current_player.execute_turn(&turn) {
+            let result = current_player.execute_turn(&turn);
+
+            if Self::stopped(&self.stop) {
+                return None;
+            }
+
+            match result {
                 TurnResult::Played(card) => {
                     player_hand.remove(player_hand.iter().position(|c| *c == card).unwrap());
                     self.discard.push(card);
 
-                    println!("{} played {}", current_player.name(), Self::get_colorized_card_name(card));
+                    self.broadcast(GameEvent::Played { player: seat, card });
 
                     match card {
                         Card::Skip { .. } => {
+                            if self.players[seat].1.is_empty() {
+                                return self.win(seat);
+                            }
 
                             self.current_player = self.next_player();
-                            let next_player = &self.players.get_mut(self.current_player).unwrap().0;
+                            let next_player = &mut self.players.get_mut(self.current_player).unwrap().0;
 
                             next_player.observe_turn_skip(None);
 
-                            println!("{}'s turn was skipped", next_player.name());
+                            self.broadcast(GameEvent::Skipped { player: self.current_player });
                             continue;
                         }
                         Card::Reverse { .. } => {
@@ -147,89 +334,42 @@ impl<'a> GameState<'a> {
 
                     current_player.observe_turn_skip(Some(cards.iter().collect()));
 
-                    println!("{} drew {} card(s)", current_player.name(), cards.len());
-
                     self.to_draw = 0;
+                    self.broadcast(GameEvent::Drew { player: seat, count: cards.len() });
                 }
             };
 
-            if player_hand.is_empty() {
-                return current_player.name().to_string();
+            if self.players[seat].1.is_empty() {
+                return self.win(seat);
             }
         }
     }
 
-pub fn get_colorized_card_name(card: Card) -> String {
-    use crate::card::CardColor::*;
-    match card {
-        Card::DrawFour { color: Some(color) } =>
-        {
-            let formatted_color = match color {
-                Red => format!("\x1b[31m{color}\x1b[0m"),
-                Blue => format!("\x1b[34m{color}\x1b[0m"),
-                Green => format!("\x1b[32m{color}\x1b[0m"),
-                Yellow => format!("\x1b[33m{color}\x1b[0m"),
-            };
-            format!("\x1b[31mDr\x1b[34maw \x1b[32mFo\x1b[33mur\x1b[0m ({formatted_color})")
-        },
-        Card::DrawTwo { color } =>
-        {
-            match color {
-                Red => format!("\x1b[31m{card}\x1b[0m"),
-                Blue => format!("\x1b[34m{card}\x1b[0m"),
-                Green => format!("\x1b[32m{card}\x1b[0m"),
-                Yellow => format!("\x1b[33m{card}\x1b[0m"),
-            }
-        },
-        Card::Skip { color } =>
-        {
-            match color {
-                Red => format!("\x1b[31m{card}\x1b[0m"),
-                Blue => format!("\x1b[34m{card}\x1b[0m"),
-                Green => format!("\x1b[32m{card}\x1b[0m"),
-                Yellow => format!("\x1b[33m{card}\x1b[0m"),
-            }
-        },
-        Card::Reverse { color } =>
-        {
-            match color {
-                Red => format!("\x1b[31m{card}\x1b[0m"),
-                Blue => format!("\x1b[34m{card}\x1b[0m"),
-                Green => format!("\x1b[32m{card}\x1b[0m"),
-                Yellow => format!("\x1b[33m{card}\x1b[0m"),
-            }
-        },
-        Card::Numeric { color, .. } =>
-        {
-            match color {
-                Red => format!("\x1b[31m{card}\x1b[0m"),
-                Blue => format!("\x1b[34m{card}\x1b[0m"),
-                Green => format!("\x1b[32m{card}\x1b[0m"),
-                Yellow => format!("\x1b[33m{card}\x1b[0m"),
-            }
-        },
-        Card::Wild { color: Some(color) } => {
-            let formatted_color = match color {
-                Red => format!("\x1b[31m{color}\x1b[0m"),
-                Blue => format!("\x1b[34m{color}\x1b[0m"),
-                Green => format!("\x1b[32m{color}\x1b[0m"),
-                Yellow => format!("\x1b[33m{color}\x1b[0m"),
-            };
-            format!("\x1b[31mW\x1b[34mi\x1b[32ml\x1b[33md\x1b[0m ({formatted_color})")
-        },
-        Card::Wild { color: None } => "\x1b[31mW\x1b[34mi\x1b[32ml\x1b[33md\x1b[0m".to_string(),
-        Card::DrawFour { color: None } => "\x1b[31mDr\x1b[34maw \x1b[32mFo\x1b[33mur\x1b[0m".to_string(),
-    }
-}
+    fn win(&mut self, seat: usize) -> Option<String> {
+        // An observer may have stopped the game on seeing the last card played.
+        if Self::stopped(&self.stop) {
+            return None;
+        }
 
-    fn get_playable_hand(hand: &[Card], card: &Card, to_draw: u8) -> Vec<Card> {
+        let winner = self.players[seat].0.name().to_string();
 
-        if to_draw > 0 && matches!(card, Card::DrawTwo { .. } | Card::DrawFour { .. }) {
-            return hand.iter().filter(|c| **c == *card).copied().collect::<Vec<Card>>();
+        if let Some(checkpoint) = &self.checkpoint {
+            *checkpoint.lock().unwrap() = None;
         }
 
+        self.broadcast(GameEvent::Won { player: seat });
+
+        Some(winner)
+    }
+
+pub fn get_colorized_card_name(card: Card) -> String {
+    theme::current().card_name(card)
+}
+
+    fn get_playable_hand(hand: &[Card], card: &Card, to_draw: u8, rules: &RuleSet) -> Vec<Card> {
+
         hand.iter()
-            .filter(|c| c.can_play_on(card))
+            .filter(|c| rules.check_play(c, card, to_draw).is_ok())
             .copied()
             .collect::<Vec<Card>>()
     }
@@ -238,7 +378,7 @@ pub fn get_colorized_card_name(card: Card) -> String {
         hand.iter().any(|c| *c == *card)
     }
 
-    fn ensure_drawable_deck(deck: &mut Deck, discard: &mut Vec<Card>, to_draw: u8) {
+    fn ensure_drawable_deck(deck: &mut Deck, discard: &mut Vec<Card>, to_draw: u8, rng: &mut StdRng) {
         if discard.len() < 2 && (deck.cards.len() as u8) >= to_draw {
             return;
         }
@@ -246,7 +386,7 @@ pub fn get_colorized_card_name(card: Card) -> String {
         if (discard.len() as u8) >= to_draw {
             let from_discard = discard.drain(..discard.len() - 1);
             deck.cards.extend(from_discard);
-            deck.shuffle();
+            deck.shuffle(rng);
 
         } else {
             // TODO: Investigate why this is necessary
@@ -256,10 +396,62 @@ pub fn get_colorized_card_name(card: Card) -> String {
             let new_deck = Deck::generate();
             deck.cards.extend(new_deck.cards);
 
-            deck.shuffle();
+            deck.shuffle(rng);
+        }
+    }
+
+    fn broadcast(&mut self, event: GameEvent) {
+        if Self::stopped(&self.stop) {
+            return;
+        }
+
+        for (player, _) in self.players.iter_mut() {
+            player.observe_event(&event);
+        }
+
+        let table = Table {
+            names: self.players.iter().map(|(p, _)| p.name()).collect(),
+            hands: self.players.iter().map(|(_, hand)| hand.as_slice()).collect(),
+            top_card: self.discard.last().copied(),
+            current_player: self.current_player,
+            direction: self.direction,
+            to_draw: self.to_draw,
+        };
+
+        for observer in self.observers.iter_mut() {
+            // Once an observer stops the game, the ones after it don't hear about the event that stopped it.
+            if Self::stopped(&self.stop) {
+                break;
+            }
+
+            observer.observe(&event, &table);
         }
     }
 
+    /// The game as it stands, with the current player to move.
+    fn position(&self) -> Position {
+        Position {
+            deck: self.deck.cards.clone(),
+            discard: self.discard.clone(),
+            hands: self.players.iter().map(|(_, hand)| hand.clone()).collect(),
+            to_move: self.current_player,
+            direction: self.direction,
+            to_draw: self.to_draw,
+        }
+    }
+
+    fn opponent_hand_sizes(&self, seat: usize) -> Vec<usize> {
+        let count = self.players.len();
+
+        (1..count)
+            .map(|offset| match self.direction {
+                Direction::Clockwise => (seat + offset) % count,
+                Direction::CounterClockwise => (seat + count - offset) % count,
+            })
+            .map(|other| self.players[other].1.len())
+            .collect()
+    }
+
     fn next_player(&self) -> usize{
         let mut index = self.current_player;
         let direction = self.direction;
@@ -0,0 +1,513 @@
+//! A reinforcement learning environment in the style of Gym, for training agents against the engine.
+//!
+//! [`Env::reset`] deals a seeded game and [`Env::step`] plays one action for the seat whose turn it is, returning
+//! what that seat's action earned and what the next seat to act can see. Some seats are agents and the rest are AIs,
+//! which play themselves between the agents' turns; with more than one agent, [`Observation::seat`] says whose turn
+//! it is, and [`Env::rewards`] hands out everyone's result when the game ends.
+//!
+//! The game runs on its own thread, like the server's rooms, with each agent seat waiting on a channel for its
+//! action. Resetting or dropping the environment stops it.
+//!
+//! `uno_cli env` serves the environment over standard input and output, one JSON object per line, for training from
+//! other languages: send `{"reset": <seed>}` or `{"step": <action>}`, and get back
+//! `{"seat", "observation", "mask", "reward", "done"}`, plus `"rewards"` for every seat once the game is over. An
+//! action the mask rules out gets `{"error"}` back, and the same seat is still waiting to act.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+
+use crate::card::{Card, CardColor};
+use crate::game::{GameState, Stop, Turn, TurnResult};
+use crate::personality::AiProfile;
+use crate::player::{AIDifficulty, Ai, Player};
+use crate::rules::RuleSet;
+
+const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+
+/// Thirteen kinds of colored card in each color, then Wild and Wild Draw Four.
+const CARD_TYPES: usize = 54;
+
+/// Every colored card type, then a Wild and a Wild Draw Four in each color, then drawing.
+pub const ACTIONS: usize = 52 + 4 + 4 + 1;
+pub const DRAW: usize = ACTIONS - 1;
+
+/// The own hand by card type, the top card's type and color in play, the pending draw, then the other hands' sizes in
+/// turn order, starting with whoever goes next, padded to the biggest table.
+pub const OBSERVATION_SIZE: usize = CARD_TYPES + CARD_TYPES + 4 + 1 + (crate::MAX_PLAYERS - 1);
+
+const WIN: f32 = 1.0;
+const LOSS: f32 = -1.0;
+
+/// What a seat sees when it's its turn: only its own hand and what's public.
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub seat: usize,
+    pub features: [f32; OBSERVATION_SIZE],
+    /// Which actions are legal. Drawing always is.
+    pub mask: [bool; ACTIONS],
+}
+
+impl Observation {
+    fn new(seat: usize, turn: &Turn) -> Observation {
+        let mut features = [0.0; OBSERVATION_SIZE];
+        let mut mask = [false; ACTIONS];
+
+        for card in turn.full_hand.iter() {
+            features[card_type(card)] += 1.0;
+        }
+
+        features[CARD_TYPES + card_type(&turn.last_card)] = 1.0;
+
+        if let Some(color) = turn.last_card.color() {
+            features[2 * CARD_TYPES + color_index(color)] = 1.0;
+        }
+
+        features[2 * CARD_TYPES + 4] = turn.to_draw as f32;
+
+        for (slot, size) in turn.opponents.iter().enumerate() {
+            features[2 * CARD_TYPES + 5 + slot] = *size as f32;
+        }
+
+        for card in turn.playable_hand.iter() {
+            match card {
+                Card::Wild { .. } => mask[52..56].fill(true),
+                Card::DrawFour { .. } => mask[56..60].fill(true),
+                card => mask[card_type(card)] = true,
+            }
+        }
+
+        mask[DRAW] = true;
+
+        Observation { seat, features, mask }
+    }
+
+    /// After the game, when nobody has anything left to do.
+    fn finished(seat: usize) -> Observation {
+        Observation { seat, features: [0.0; OBSERVATION_SIZE], mask: [false; ACTIONS] }
+    }
+}
+
+fn color_index(color: CardColor) -> usize {
+    COLORS.iter().position(|c| *c == color).unwrap()
+}
+
+fn card_type(card: &Card) -> usize {
+    match *card {
+        Card::Numeric { color, value } => color_index(color) * 13 + value.number() as usize,
+        Card::Skip { color } => color_index(color) * 13 + 10,
+        Card::Reverse { color } => color_index(color) * 13 + 11,
+        Card::DrawTwo { color } => color_index(color) * 13 + 12,
+        Card::Wild { .. } => 52,
+        Card::DrawFour { .. } => 53,
+    }
+}
+
+/// What an action does on `turn`. [`Env::step`] only passes on legal actions, but anything else would draw.
+fn decode(action: usize, turn: &Turn) -> TurnResult {
+    let card = match action {
+        52..=55 => Some(Card::Wild { color: Some(COLORS[action - 52]) }),
+        56..=59 => Some(Card::DrawFour { color: Some(COLORS[action - 56]) }),
+        _ => turn.playable_hand.iter().find(|c| action < 52 && card_type(c) == action).copied(),
+    };
+
+    match card {
+        // Wild cards match whatever color they've been given, so this finds one in hand for both.
+        Some(card) if turn.playable_hand.contains(&card) => TurnResult::Played(card),
+        _ => TurnResult::Drew,
+    }
+}
+
+enum Message {
+    Turn { seat: usize, turn: Turn },
+    Finished { winner: Option<usize> },
+    /// The game panicked, e.g. in one of the AIs.
+    Failed(String),
+}
+
+/// An agent's seat in the game thread, which passes its turns out to the [`Env`] and waits for the action.
+struct AgentSeat {
+    seat: usize,
+    name: String,
+    messages: Sender<Message>,
+    actions: Receiver<usize>,
+}
+
+impl Player for AgentSeat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
+        let _ = self.messages.send(Message::Turn { seat: self.seat, turn: turn.clone() });
+
+        match self.actions.recv() {
+            Ok(action) => decode(action, turn),
+            // The environment was reset or dropped, which stops the game before this move is made.
+            Err(_) => TurnResult::Drew,
+        }
+    }
+
+    fn observe_turn(&self, _other: &dyn Player, _card: &Card) {}
+
+    fn observe_turn_skip(&mut self, _observed_cards: Option<Vec<&Card>>) {}
+}
+
+struct Running {
+    messages: Receiver<Message>,
+    /// One per seat; AI seats don't need one.
+    actions: Vec<Option<Sender<usize>>>,
+    /// The seat waiting for an action, and which actions it may take.
+    waiting: Option<(usize, [bool; ACTIONS])>,
+    stop: Stop,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        // Set before the action channels close, so a seat waiting on one finds the game already stopped.
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+pub struct Env {
+    rules: RuleSet,
+    /// `None` for an agent, or the AI playing the seat.
+    seats: Vec<Option<AiProfile>>,
+    game: Option<Running>,
+    rewards: Vec<f32>,
+}
+
+impl Env {
+    /// `agents` seats for learners, followed by an AI of `difficulty` in each of the `ais` seats after them.
+    pub fn new(rules: RuleSet, agents: usize, ais: usize, difficulty: AIDifficulty) -> Env {
+        let mut seats = vec![None; agents];
+        seats.extend((0..ais).map(|_| Some(AiProfile::for_difficulty(difficulty))));
+
+        Env { rules, seats, game: None, rewards: vec![] }
+    }
+
+    /// Deals a new game from `seed`, abandoning any game in progress, and returns what the first agent to act sees.
+    pub fn reset(&mut self, seed: u64) -> Result<Observation, String> {
+        // Dropping the old game stops it.
+        self.game = None;
+        self.rewards = vec![0.0; self.seats.len()];
+
+        let (messages, inbox) = channel();
+        let mut actions = vec![];
+        let mut players = vec![];
+
+        for (seat, profile) in self.seats.iter().enumerate() {
+            let name = format!("seat {seat}");
+
+            match profile {
+                Some(profile) => {
+                    let mut ai = Ai::with_profile(StdRng::seed_from_u64(seed.wrapping_add(seat as u64 + 1)), profile.clone());
+                    ai.set_name(&name);
+                    players.push(Box::new(ai) as Box<dyn Player>);
+                    actions.push(None);
+                }
+                None => {
+                    let (sender, receiver) = channel();
+                    players.push(Box::new(AgentSeat { seat, name, messages: messages.clone(), actions: receiver }));
+                    actions.push(Some(sender));
+                }
+            }
+        }
+
+        let rules = self.rules;
+        let stop = Stop::new(AtomicBool::new(false));
+        let game_stop = stop.clone();
+
+        std::thread::spawn(move || {
+            let mut game = GameState::new(players, rules);
+            game.set_seed(seed);
+            game.set_turn_delay(Duration::ZERO);
+            game.set_stop(game_stop);
+
+            let message = match std::panic::catch_unwind(AssertUnwindSafe(|| game.start())) {
+                Ok(Some(winner)) => Message::Finished { winner: winner.strip_prefix("seat ").and_then(|seat| seat.parse().ok()) },
+                // Stopped, so nobody is waiting to hear about it.
+                Ok(None) => return,
+                Err(panic) => {
+                    let reason = panic.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| panic.downcast_ref::<String>().cloned());
+                    Message::Failed(reason.unwrap_or_else(|| "no reason given".to_string()))
+                }
+            };
+
+            let _ = messages.send(message);
+        });
+
+        self.game = Some(Running { messages: inbox, actions, waiting: None, stop });
+
+        // With tiny hands an AI can go out before any agent has a turn.
+        let first = self.seats.iter().position(Option::is_none).unwrap_or(0);
+        Ok(self.advance()?.unwrap_or(Observation::finished(first)))
+    }
+
+    /// Plays `action` for the seat whose turn it is. Returns what the next seat to act sees, the reward for the seat
+    /// that just acted, and whether the game is over. An action that isn't legal is refused, and the seat keeps its
+    /// turn.
+    pub fn step(&mut self, action: usize) -> Result<(Observation, f32, bool), String> {
+        let Some(game) = self.game.as_mut() else {
+            return Err("The game is over; reset to start another.".to_string());
+        };
+        let (seat, mask) = game.waiting.ok_or("No seat is waiting to act; reset to start a game.")?;
+
+        if !mask.get(action).copied().unwrap_or(false) {
+            return Err(format!("Action {action} isn't legal for seat {seat} right now; the mask says which are."));
+        }
+
+        game.waiting = None;
+
+        if let Some(actions) = &game.actions[seat] {
+            let _ = actions.send(action);
+        }
+
+        // Rewards only come at the end, so until then this is 0.
+        Ok(match self.advance()? {
+            Some(observation) => (observation, self.rewards[seat], false),
+            None => (Observation::finished(seat), self.rewards[seat], true),
+        })
+    }
+
+    pub fn done(&self) -> bool {
+        self.game.is_none()
+    }
+
+    /// Each seat's result once the game is over: a win or a loss.
+    pub fn rewards(&self) -> &[f32] {
+        &self.rewards
+    }
+
+    /// Waits for the next agent's turn, or for the game to end, in which case there's nothing to observe. If the game
+    /// fails instead, it's over without a result.
+    fn advance(&mut self) -> Result<Option<Observation>, String> {
+        let game = self.game.as_mut().unwrap();
+
+        let error = match game.messages.recv() {
+            Ok(Message::Turn { seat, turn }) => {
+                let observation = Observation::new(seat, &turn);
+                game.waiting = Some((seat, observation.mask));
+                return Ok(Some(observation));
+            }
+            Ok(Message::Finished { winner }) => {
+                for (seat, reward) in self.rewards.iter_mut().enumerate() {
+                    *reward = if Some(seat) == winner { WIN } else { LOSS };
+                }
+
+                self.game = None;
+                return Ok(None);
+            }
+            Ok(Message::Failed(reason)) => format!("The game failed: {reason}. Reset to start another."),
+            Err(_) => "The game stopped unexpectedly. Reset to start another.".to_string(),
+        };
+
+        self.game = None;
+        Err(error)
+    }
+}
+
+const USAGE: &str = "\
+Usage: env [--agents N] [--ai N] [--difficulty easy|medium|hard] [--hand-size N] [--no-stacking]
+Then send one JSON object per line: {\"reset\": <seed>} or {\"step\": <action>}.";
+
+fn respond(observation: &Observation, reward: f32, done: bool, env: &Env) -> Value {
+    let mut response = json!({
+        "seat": observation.seat,
+        "observation": observation.features.to_vec(),
+        "mask": observation.mask.to_vec(),
+        "reward": reward,
+        "done": done,
+    });
+
+    if done {
+        response["rewards"] = json!(env.rewards());
+    }
+
+    response
+}
+
+fn parse(args: &[String]) -> Result<Env, String> {
+    let (mut agents, mut ais, mut difficulty, mut rules) = (1, 3, AIDifficulty::Hard, RuleSet::default());
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value.\n{USAGE}"));
+        let number = |value: &String| value.parse::<usize>().map_err(|_| format!("{value} is not a number."));
+
+        match arg.as_str() {
+            "--agents" => agents = number(value()?)?,
+            "--ai" => ais = number(value()?)?,
+            "--difficulty" => difficulty = value()?.parse()?,
+            "--hand-size" => rules.hand_size = number(value()?)?.try_into().map_err(|_| "That hand is too big.")?,
+            "--no-stacking" => rules.stacking = false,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    if agents == 0 || !(2..=crate::MAX_PLAYERS).contains(&(agents + ais)) {
+        return Err(format!("The table needs at least one agent, and between 2 and {} players.", crate::MAX_PLAYERS));
+    }
+
+    Ok(Env::new(rules, agents, ais, difficulty))
+}
+
+/// Serves an environment over standard input and output until input runs out.
+pub fn run(args: &[String]) {
+    let mut env = match parse(args) {
+        Ok(env) => env,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    for line in std::io::stdin().lines() {
+        let Ok(line) = line else { break };
+        let request: Value = serde_json::from_str(&line).unwrap_or_default();
+
+        let response = match (request["reset"].as_u64(), request["step"].as_u64()) {
+            (Some(seed), _) => match env.reset(seed) {
+                Ok(observation) => respond(&observation, 0.0, env.done(), &env),
+                Err(e) => json!({ "error": e }),
+            },
+            (None, Some(action)) => match env.step(action as usize) {
+                Ok((observation, reward, done)) => respond(&observation, reward, done, &env),
+                Err(e) => json!({ "error": e }),
+            },
+            _ => json!({ "error": format!("Expected {{\"reset\": <seed>}} or {{\"step\": <action>}}, not {line}.") }),
+        };
+
+        println!("{response}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{CardValue, Deck};
+
+    /// Own hand size, and the size of the hand that plays next.
+    fn hand_sizes(observation: &Observation) -> (f32, f32) {
+        (observation.features[..CARD_TYPES].iter().sum(), observation.features[2 * CARD_TYPES + 5])
+    }
+
+    /// The first legal card, or drawing if there's none.
+    fn first_legal(observation: &Observation) -> usize {
+        observation.mask.iter().position(|legal| *legal).unwrap()
+    }
+
+    #[test]
+    fn the_same_seed_deals_the_same_game() {
+        let mut first = Env::new(RuleSet::default(), 1, 3, AIDifficulty::Hard);
+        let mut second = Env::new(RuleSet::default(), 1, 3, AIDifficulty::Hard);
+        let (mut a, mut b) = (first.reset(11).unwrap(), second.reset(11).unwrap());
+
+        while !first.done() {
+            assert_eq!((a.seat, a.features, a.mask), (b.seat, b.features, b.mask));
+
+            let action = first_legal(&a);
+            let (next_a, reward_a, done_a) = first.step(action).unwrap();
+            let (next_b, reward_b, done_b) = second.step(action).unwrap();
+
+            assert_eq!((reward_a, done_a), (reward_b, done_b));
+            (a, b) = (next_a, next_b);
+        }
+
+        assert_eq!(first.rewards(), second.rewards());
+    }
+
+    #[test]
+    fn the_mask_matches_what_actions_do() {
+        let rules = RuleSet::default();
+        let tops = [
+            Card::Numeric { color: CardColor::Green, value: CardValue::Seven },
+            Card::Skip { color: CardColor::Yellow },
+            Card::DrawTwo { color: CardColor::Red },
+            Card::Wild { color: Some(CardColor::Blue) },
+        ];
+
+        for (top, to_draw) in tops.iter().flat_map(|top| [(*top, 0), (*top, 2)]) {
+            let full_hand = Deck::generate().cards;
+            let playable_hand = full_hand.iter().filter(|c| rules.check_play(c, &top, to_draw).is_ok()).copied().collect();
+            let turn = Turn { to_draw, full_hand, playable_hand, last_card: top, rules, opponents: vec![7, 7] };
+            let mask = Observation::new(0, &turn).mask;
+
+            for (action, legal) in mask.iter().enumerate().take(DRAW) {
+                match (*legal, decode(action, &turn)) {
+                    (true, TurnResult::Played(card)) => assert!(turn.playable_hand.contains(&card), "{action} on {top}"),
+                    (false, TurnResult::Drew) => {}
+                    (legal, result) => panic!("{action} on {top} is legal: {legal}, but gives {result:?}"),
+                }
+            }
+
+            assert!(mask[DRAW]);
+            assert!(matches!(decode(DRAW, &turn), TurnResult::Drew));
+        }
+    }
+
+    #[test]
+    fn refuses_illegal_actions() {
+        let mut env = Env::new(RuleSet::default(), 1, 1, AIDifficulty::Easy);
+        let observation = env.reset(3).unwrap();
+        let illegal = observation.mask.iter().position(|legal| !legal).unwrap();
+
+        assert!(env.step(illegal).is_err());
+        assert!(env.step(ACTIONS).is_err());
+
+        // The seat still has its turn.
+        assert!(env.step(DRAW).is_ok());
+    }
+
+    #[test]
+    fn two_agents_take_their_own_turns() {
+        let mut env = Env::new(RuleSet::default(), 2, 0, AIDifficulty::Easy);
+        let mut observation = env.reset(5).unwrap();
+
+        // Seat 0 deals, so seat 1 goes first.
+        assert_eq!(observation.seat, 1);
+
+        loop {
+            let seat = observation.seat;
+            let (own, other) = hand_sizes(&observation);
+            let action = first_legal(&observation);
+            let (next, reward, done) = env.step(action).unwrap();
+
+            // Nobody else acts in between, so a game that ends here was won by this seat.
+            if done {
+                assert_eq!((reward, env.rewards()[seat]), (WIN, WIN));
+                break;
+            }
+
+            // Whoever acts next is holding the hand they were just seen holding.
+            if next.seat != seat {
+                assert_eq!(hand_sizes(&next).0, other, "seat {} after seat {seat}", next.seat);
+            } else if action != DRAW {
+                assert_eq!(hand_sizes(&next).0, own - 1.0, "seat {seat} again");
+            }
+
+            observation = next;
+        }
+
+        assert_eq!(env.rewards().iter().sum::<f32>(), WIN + LOSS);
+    }
+
+    #[test]
+    fn rewards_add_up_to_one_win_and_a_loss_for_everyone_else() {
+        let mut env = Env::new(RuleSet::default(), 2, 2, AIDifficulty::Hard);
+        let mut observation = env.reset(8).unwrap();
+
+        while !env.done() {
+            assert!(observation.seat < 2, "seat {} is an AI", observation.seat);
+            observation = env.step(first_legal(&observation)).unwrap().0;
+        }
+
+        assert_eq!(env.rewards().iter().filter(|r| **r == WIN).count(), 1);
+        assert_eq!(env.rewards().iter().sum::<f32>(), WIN + LOSS * 3.0);
+    }
+}
@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use crate::card::{Card, CardColor};
+
+/// How a hand is ordered on screen. Card numbers in commands follow the same order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HandSort {
+    /// Grouped by color, each group sorted by value, wild cards last.
+    Color,
+    /// Sorted by value, then color.
+    Value,
+    /// Playable cards first, each half grouped by color.
+    Playable,
+}
+
+impl FromStr for HandSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c" | "color" => Ok(HandSort::Color),
+            "v" | "value" => Ok(HandSort::Value),
+            "p" | "playable" => Ok(HandSort::Playable),
+            _ => Err(format!("{s} is not a sort order; use color, value or playable")),
+        }
+    }
+}
+
+fn color_rank(card: &Card) -> u8 {
+    match card.color() {
+        _ if matches!(card, Card::Wild { .. } | Card::DrawFour { .. }) => 4,
+        Some(CardColor::Red) => 0,
+        Some(CardColor::Green) => 1,
+        Some(CardColor::Blue) => 2,
+        Some(CardColor::Yellow) => 3,
+        None => 4,
+    }
+}
+
+fn value_rank(card: &Card) -> u8 {
+    match card {
+        Card::Numeric { value, .. } => value.number(),
+        Card::Skip { .. } => 10,
+        Card::Reverse { .. } => 11,
+        Card::DrawTwo { .. } => 12,
+        Card::Wild { .. } => 13,
+        Card::DrawFour { .. } => 14,
+    }
+}
+
+pub fn sort_hand(hand: &[Card], playable: &[Card], order: HandSort) -> Vec<Card> {
+    let mut sorted = hand.to_vec();
+
+    match order {
+        HandSort::Color => sorted.sort_by_key(|c| (color_rank(c), value_rank(c))),
+        HandSort::Value => sorted.sort_by_key(|c| (value_rank(c), color_rank(c))),
+        HandSort::Playable => sorted.sort_by_key(|c| (!playable.contains(c), color_rank(c), value_rank(c))),
+    }
+
+    sorted
+}
@@ -0,0 +1,344 @@
+//! Match history, kept in an SQLite database in the data directory behind the `history` feature. Every finished game
+//! is stored with its players, rules, seed, scores and the full game record, so any of them can be exported and
+//! replayed later.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::game::{GameEvent, GameObserver, Table};
+use crate::player::AIDifficulty;
+use crate::profile::Profiles;
+use crate::record::RecordWriter;
+use crate::rules::RuleSet;
+use crate::save::{self, Seat};
+
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS matches (
+        id INTEGER PRIMARY KEY,
+        finished_at INTEGER NOT NULL,
+        -- NULL for games picked up from a save, which can't be replayed from the deal.
+        seed INTEGER,
+        hand_size INTEGER NOT NULL,
+        stacking INTEGER NOT NULL,
+        -- NULL when there were no AIs at the table.
+        difficulty TEXT,
+        winner INTEGER NOT NULL,
+        -- The game record, as written by `--record`.
+        record TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS match_players (
+        match_id INTEGER NOT NULL REFERENCES matches (id),
+        seat INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        human INTEGER NOT NULL,
+        cards_left INTEGER NOT NULL,
+        score INTEGER NOT NULL,
+        PRIMARY KEY (match_id, seat)
+    );
+
+    CREATE INDEX IF NOT EXISTS match_players_name ON match_players (name COLLATE NOCASE);
+";
+
+const USAGE: &str = "\
+Usage:
+  history [N]                  your last N games (10 if not given)
+  history vs <name>            your games against someone, and who won them
+  history seats                win rate by position in the turn order, over every game
+  history export <id> <file>   write a game's record to a file, to watch with replay
+Add --player <name> to look at someone else's games.";
+
+fn path() -> PathBuf {
+    save::data_dir().join("history.sqlite")
+}
+
+fn open() -> Result<Connection, String> {
+    let error = |e: &dyn std::fmt::Display| format!("Could not open the match history at {}: {e}", path().display());
+
+    std::fs::create_dir_all(save::data_dir()).map_err(|e| error(&e))?;
+
+    let db = Connection::open(path()).map_err(|e| error(&e))?;
+    // Server rooms finishing at the same time take turns writing.
+    db.busy_timeout(Duration::from_secs(5)).map_err(|e| error(&e))?;
+    let version: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(|e| error(&e))?;
+
+    if version > SCHEMA_VERSION {
+        return Err(error(&format!("it was written by a newer version (schema {version})")));
+    }
+
+    db.execute_batch(SCHEMA).map_err(|e| error(&e))?;
+    db.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(|e| error(&e))?;
+
+    Ok(db)
+}
+
+/// Stores every game that's played to the end.
+pub struct MatchLogger {
+    seats: Vec<Seat>,
+    difficulty: Option<AIDifficulty>,
+    rules: RuleSet,
+    seed: Option<u64>,
+    record: Option<RecordWriter<Vec<u8>>>,
+}
+
+impl MatchLogger {
+    /// `seed` is `None` for a game picked up from a save: it's stored without a record, since its start is missing.
+    pub fn new(seats: &[Seat], difficulty: Option<AIDifficulty>, rules: RuleSet, seed: Option<u64>) -> MatchLogger {
+        MatchLogger {
+            seats: seats.to_vec(),
+            difficulty,
+            rules,
+            seed,
+            record: seed.map(|seed| RecordWriter::new(vec![], seed, rules)),
+        }
+    }
+
+    fn store(&self, winner: usize, table: &Table) -> Result<(), String> {
+        let mut db = open()?;
+        let error = |e: rusqlite::Error| format!("Could not add the game to the match history: {e}");
+
+        let points = table.hands.iter().map(|hand| hand.iter().map(|c| c.points()).sum::<u32>()).collect::<Vec<u32>>();
+        let finished_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+        let record = self.record.as_ref().map(|record| String::from_utf8_lossy(record.output()).into_owned());
+
+        let transaction = db.transaction().map_err(error)?;
+
+        transaction
+            .execute(
+                "INSERT INTO matches (finished_at, seed, hand_size, stacking, difficulty, winner, record) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    finished_at,
+                    // SQLite integers are signed; the bits are what matter.
+                    self.seed.map(|seed| seed as i64),
+                    self.rules.hand_size,
+                    self.rules.stacking,
+                    self.difficulty.map(|d| d.to_string()),
+                    winner as i64,
+                    record,
+                ],
+            )
+            .map_err(error)?;
+
+        let match_id = transaction.last_insert_rowid();
+
+        for (seat, player) in self.seats.iter().enumerate() {
+            let score = if seat == winner { points.iter().sum::<u32>() } else { 0 };
+
+            transaction
+                .execute(
+                    "INSERT INTO match_players (match_id, seat, name, human, cards_left, score) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![match_id, seat as i64, player.name, player.human, table.hands.get(seat).map_or(0, |h| h.len() as i64), score],
+                )
+                .map_err(error)?;
+        }
+
+        transaction.commit().map_err(error)
+    }
+}
+
+impl GameObserver for MatchLogger {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        if let Some(record) = self.record.as_mut() {
+            record.observe(event, table);
+        }
+
+        if let GameEvent::Won { player } = event {
+            if let Err(e) = self.store(*player, table) {
+                println!("{e}");
+            }
+        }
+    }
+}
+
+/// `secs` since the Unix epoch as a UTC date and time, e.g. `2024-03-09 18:30`.
+fn format_time(secs: i64) -> String {
+    let (days, time) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!("{year}-{month:02}-{day:02} {:02}:{:02}", time / 3600, time % 3600 / 60)
+}
+
+fn last_games(db: &Connection, player: &str, count: usize) -> rusqlite::Result<()> {
+    let mut query = db.prepare(
+        "SELECT m.id, m.finished_at, m.difficulty, p.seat = m.winner, p.cards_left, p.score,
+            (SELECT name FROM match_players w WHERE w.match_id = m.id AND w.seat = m.winner),
+            (SELECT group_concat(name, ', ') FROM match_players o WHERE o.match_id = m.id AND o.seat != p.seat)
+         FROM matches m JOIN match_players p ON p.match_id = m.id
+         WHERE p.name = ?1 COLLATE NOCASE
+         ORDER BY m.id DESC LIMIT ?2",
+    )?;
+
+    let rows = query
+        .query_map(params![player, count as i64], |row| {
+            let (id, finished_at, difficulty, won): (i64, i64, Option<String>, bool) = (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?);
+            let (cards_left, score, winner, others): (u32, u32, String, String) = (row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?);
+
+            let result = if won { format!("won, {score} points") } else { format!("lost to {winner}, {cards_left} cards left") };
+            let against = match difficulty {
+                Some(difficulty) => format!("{others} ({difficulty} AIs)"),
+                None => others,
+            };
+
+            Ok(format!("#{id:<5} {}  vs {against}: {result}", format_time(finished_at)))
+        })?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    if rows.is_empty() {
+        println!("{player} hasn't finished any games yet.");
+    }
+
+    for row in rows {
+        println!("{row}");
+    }
+
+    Ok(())
+}
+
+fn head_to_head(db: &Connection, player: &str, other: &str) -> rusqlite::Result<()> {
+    let (games, player_wins, other_wins): (u32, Option<u32>, Option<u32>) = db.query_row(
+        "SELECT COUNT(*), SUM(m.winner = a.seat), SUM(m.winner = b.seat)
+         FROM matches m
+         JOIN match_players a ON a.match_id = m.id AND a.name = ?1 COLLATE NOCASE
+         JOIN match_players b ON b.match_id = m.id AND b.name = ?2 COLLATE NOCASE AND b.seat != a.seat",
+        params![player, other],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    if games == 0 {
+        println!("{player} and {other} haven't finished a game together yet.");
+        return Ok(());
+    }
+
+    let (player_wins, other_wins) = (player_wins.unwrap_or(0), other_wins.unwrap_or(0));
+
+    println!("{player} vs {other}: {games} games");
+    println!("  {player} won {player_wins} ({:.0}%)", player_wins as f64 / games as f64 * 100.0);
+    println!("  {other} won {other_wins} ({:.0}%)", other_wins as f64 / games as f64 * 100.0);
+
+    if games > player_wins + other_wins {
+        println!("  Someone else won {}", games - player_wins - other_wins);
+    }
+
+    Ok(())
+}
+
+fn seat_win_rates(db: &Connection) -> rusqlite::Result<()> {
+    let mut query = db.prepare(
+        "SELECT p.seat, (SELECT COUNT(*) FROM match_players o WHERE o.match_id = m.id), p.seat = m.winner
+         FROM matches m JOIN match_players p ON p.match_id = m.id
+         WHERE m.seed IS NOT NULL",
+    )?;
+
+    let mut positions: Vec<(u32, u32)> = vec![];
+
+    for row in query.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, bool>(2)?)))? {
+        let (seat, players, won) = row?;
+        let (seat, players) = (seat as usize, players as usize);
+        // The seat after the first one moves first. That only holds for games played from the start, which is why
+        // the ones picked up from a save (stored without a seed) are left out.
+        let position = (seat + players - 1) % players;
+
+        if positions.len() <= position {
+            positions.resize(position + 1, (0, 0));
+        }
+
+        positions[position].0 += 1;
+        positions[position].1 += won as u32;
+    }
+
+    if positions.is_empty() {
+        println!("No games have been finished yet.");
+    }
+
+    for (position, (games, wins)) in positions.iter().enumerate() {
+        let ordinal = match position + 1 {
+            1 => "1st".to_string(),
+            2 => "2nd".to_string(),
+            3 => "3rd".to_string(),
+            n => format!("{n}th"),
+        };
+
+        println!("{ordinal} to play: {wins} won of {games} ({:.0}%)", *wins as f64 / (*games).max(1) as f64 * 100.0);
+    }
+
+    Ok(())
+}
+
+fn export(db: &Connection, id: i64, file: &Path) -> Result<(), String> {
+    let record: Option<Option<String>> = db
+        .query_row("SELECT record FROM matches WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match record {
+        None => Err(format!("There's no game #{id} in the history.")),
+        Some(None) => Err(format!("Game #{id} was picked up from a save, so there's no record of it from the deal.")),
+        Some(Some(record)) => {
+            std::fs::write(file, record).map_err(|e| format!("Could not write {}: {e}", file.display()))?;
+            println!("Wrote game #{id} to {}. Watch it with: uno_cli replay {}", file.display(), file.display());
+            Ok(())
+        }
+    }
+}
+
+/// Runs a `history` query; `args` are the words after `history`.
+pub fn run(args: &[String]) {
+    let mut player = None;
+    let mut words = vec![];
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--player" => player = args.next().cloned(),
+            _ => words.push(arg.as_str()),
+        }
+    }
+
+    let player = player.or_else(|| Profiles::load().ok()?.last);
+
+    let db = match open() {
+        Ok(db) => db,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    let needs_player = |query: &dyn Fn(&str) -> rusqlite::Result<()>| match &player {
+        Some(player) => query(player).map_err(|e| e.to_string()),
+        None => Err("Whose games? Add --player <name>.".to_string()),
+    };
+
+    let result = match words.as_slice() {
+        [] => needs_player(&|player| last_games(&db, player, 10)),
+        ["vs", other] => needs_player(&|player| head_to_head(&db, player, other)),
+        ["seats"] => seat_win_rates(&db).map_err(|e| e.to_string()),
+        ["export", id, file] => match id.trim_start_matches('#').parse() {
+            Ok(id) => export(&db, id, Path::new(file)),
+            Err(_) => Err(format!("{id} is not a game number.")),
+        },
+        [count] => match count.parse() {
+            Ok(count) => needs_player(&|player| last_games(&db, player, count)),
+            Err(_) => Err(USAGE.to_string()),
+        },
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(e) = result {
+        println!("{e}");
+    }
+}
@@ -1,85 +1,537 @@
-#![feature(slice_group_by)]
 
 use std::io::{stdout, Write};
-use rand::thread_rng;
-use crate::game::GameState;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::adaptive::{Adaptive, Adjuster};
+use crate::art::Charset;
+use crate::cli::Options;
+use crate::console::{Announcer, Spectator, Transcript};
+use crate::game::{Checkpoint, GameState};
+#[cfg(feature = "history")]
+use crate::history::MatchLogger;
+use crate::personality::AiProfile;
 use crate::player::{AIDifficulty, Player};
+use crate::profile::{ProfileTracker, Profiles};
+use crate::record::RecordWriter;
+use crate::save::{SavedGame, Seat};
+use crate::review::Recorder;
+use crate::rules::RuleSet;
+use crate::theme::{Palette, Theme};
+use crate::tui::{Screen, Session, TuiPlayer, TuiView};
 
+mod adaptive;
+mod art;
 mod card;
+mod cli;
+mod coach;
+mod command;
+mod console;
+mod deadline;
+mod personality;
 mod player;
 mod game;
+mod gym;
+mod hand;
+#[cfg(feature = "history")]
+mod history;
+mod profile;
+mod rating;
+mod record;
+mod replay;
+mod review;
+mod rules;
+mod save;
+mod serialization;
+mod server;
+mod theme;
+mod tui;
+mod tune;
 
 fn main() {
+    let options = Options::parse();
 
-    let difficulty = get_difficulty();
+    if let Some(address) = options.serve {
+        if let Err(e) = server::run(&address) {
+            println!("Could not start the server on {address}: {e}");
+        }
+
+        return;
+    }
+
+    let mut theme = Theme::detect();
+    theme.palette = options.palette.unwrap_or(theme.palette);
+    theme.colorblind = options.colorblind;
+
+    if options.accessible {
+        // Color words are already in every card name; glyphs and escape codes only get read out as noise.
+        theme = Theme { palette: Palette::Monochrome, colorblind: false };
+    }
+
+    // Older Windows consoles can't show escape codes at all.
+    if theme.palette != Palette::Monochrome && enable_ansi_support::enable_ansi_support().is_err() {
+        theme.palette = Palette::Monochrome;
+    }
+
+    theme::set(theme);
+
+    if let Some(name) = options.stats {
+        profile::print_stats(name.as_deref());
+        return;
+    }
+
+    if let Some(query) = options.history {
+        #[cfg(feature = "history")]
+        history::run(&query);
+        #[cfg(not(feature = "history"))]
+        println!("This build has no match history; {} can't be looked up.", query.join(" "));
+        return;
+    }
+
+    if let Some(args) = options.tune {
+        tune::run(&args);
+        return;
+    }
+
+    if let Some(args) = options.env {
+        gym::run(&args);
+        return;
+    }
+
+    if options.leaderboard {
+        profile::print_leaderboard();
+        return;
+    }
+
+    if let Some(path) = options.replay {
+        replay::run(&path);
+        return;
+    }
+
+    if let Some(path) = options.verify {
+        if !record::verify(&path) {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let saved = match options.load.as_deref().map(SavedGame::load) {
+        Some(Ok(saved)) => Some(saved),
+        Some(Err(e)) => {
+            println!("{e}");
+            return;
+        }
+        None => None,
+    };
+
+    // A saved game brings its own seats.
+    let spectate = options.spectate && saved.is_none();
+    let humans = match &saved {
+        Some(saved) => saved.seats.iter().filter(|s| s.human).count(),
+        None if spectate => 0,
+        None => options.humans,
+    };
+    let ai_players = match &saved {
+        Some(saved) => saved.seats.len() - humans,
+        None => options.ai_players.unwrap_or(4_usize.saturating_sub(humans)),
+    };
+    let rules = saved.as_ref().map_or(RuleSet::default(), |s| s.rules);
+    // Screen reader users set their own pace, so accessible mode never sleeps.
+    let delay = if options.accessible { Duration::ZERO } else { Duration::from_millis(options.delay.unwrap_or(800)) };
+    let pause = if options.accessible { Duration::ZERO } else { Duration::from_millis(1500) };
+
+    if !(2..=MAX_PLAYERS).contains(&(humans + ai_players)) {
+        println!("A game needs between 2 and {MAX_PLAYERS} players.");
+        return;
+    }
+
+    if options.tui && options.accessible {
+        println!("The full-screen UI can't be combined with accessible mode.");
+        return;
+    }
+
+    if options.tui && humans != 1 {
+        println!("The full-screen UI is for one player against the computer.");
+        return;
+    }
+
+    let personalities = match personality::available(options.personality_file.as_deref()) {
+        Ok(personalities) => personalities,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+    let find_personality = |name: &str| personalities.iter().find(|p| p.personality.eq_ignore_ascii_case(name));
+    let mut chosen = vec![];
+
+    for name in options.personalities.iter() {
+        match find_personality(name) {
+            Some(profile) => chosen.push(profile),
+            None => {
+                let names = personalities.iter().map(|p| p.personality.as_str()).collect::<Vec<&str>>();
+                println!("There's no personality called {name}. Try one of: {}.", names.join(", "));
+                return;
+            }
+        }
+    }
 
-    enable_ansi_support::enable_ansi_support().unwrap();
+    if chosen.len() > ai_players && saved.is_none() {
+        println!("There are only {ai_players} AI seats, so only the first {ai_players} personalities will play.");
+    }
 
     println!("Welcome to Uno (CLI Edition!). The game will start shortly.");
 
-    std::thread::sleep(std::time::Duration::from_millis(1500));
+    std::thread::sleep(pause);
+
+    let hot_seat = humans > 1;
+    let mut human_names = vec![];
+
+    if let Some(saved) = &saved {
+        human_names.extend(saved.seats.iter().filter(|s| s.human).map(|s| s.name.clone()));
+    }
 
-    let mut rng = &mut thread_rng();
-    let mut rng2 = &mut thread_rng();
-    let mut rng3 = &mut thread_rng();
+    let profiles = Profiles::load();
 
-    let ai_one = &mut player::Ai::new(&mut rng, difficulty);
-    let ai_two = &mut player::Ai::new(&mut rng2, difficulty);
-    let ai_three = &mut player::Ai::new(&mut rng3, difficulty);
+    if let Err(e) = &profiles {
+        println!("{e}");
+    }
 
-    println!("Lets start with your name: ");
-    let mut name = String::new();
-    std::io::stdin().read_line(&mut name).unwrap();
+    let known = profiles.clone().unwrap_or_default();
 
-    let human = &mut player::Human::new(name.trim().to_string());
+    // Whoever played last on this computer only has to press Enter.
+    let last = profiles.as_ref().ok().and_then(|p| p.last.clone()).filter(|_| !hot_seat);
 
-    loop {
-        let players: Vec<&mut dyn Player> = vec![ai_one, human, ai_two, ai_three];
-        let mut game = GameState::new(players);
+    // Who won is told by name, so nobody can go by a name one of the AIs might have.
+    let ai_name = |name: &str| {
+        let personality_names = chosen.iter().flat_map(|p| p.names.iter().map(String::as_str));
+        player::AI_NAMES.iter().copied().chain(personality_names).any(|n| n.eq_ignore_ascii_case(name))
+    };
+
+    for seat in human_names.len()..humans {
+        loop {
+            match &last {
+                _ if hot_seat => println!("Player {}, what's your name?", seat + 1),
+                Some(last) => println!("Lets start with your name (press Enter for {last}): "),
+                None => println!("Lets start with your name: "),
+            }
+
+            let mut name = String::new();
+            std::io::stdin().read_line(&mut name).unwrap();
+
+            let name = match (name.trim(), &last) {
+                ("", Some(last)) => last.clone(),
+                (name, _) => name.to_string(),
+            };
+
+            if ai_name(&name) {
+                println!("{name} is taken by one of the computer players. Please pick another name.");
+                continue;
+            }
+
+            // Who won is told by name, so two people can't share one.
+            if human_names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                println!("{name} is already at the table. Please pick another name.");
+                continue;
+            }
+
+            human_names.push(name);
+            break;
+        }
+    }
+
+    if let (Ok(mut profiles), false, Some(name)) = (profiles, hot_seat, human_names.first()) {
+        profiles.last = Some(name.clone());
+
+        if let Err(e) = profiles.save() {
+            println!("{e}");
+        }
+    }
+
+    for name in human_names.iter() {
+        println!("{name}, your rating is {:.0}.", known.rating(name));
+    }
+
+    let rating = human_names.iter().map(|n| known.rating(n)).sum::<f64>() / humans.max(1) as f64;
+    let difficulty = match &saved {
+        Some(saved) => saved.difficulty,
+        None if ai_players == 0 => AIDifficulty::Medium,
+        None if options.matchmaking && humans > 0 => {
+            let difficulty = known.closest_difficulty(rating);
+
+            println!("Matched with {difficulty} AIs, rated {:.0}.", known.ai_rating(difficulty));
+            difficulty
+        }
+        // Adaptive AIs start wherever they're asked to, or close to the players' rating, and find their own level.
+        None if options.adaptive && humans > 0 => options.difficulty.unwrap_or(known.closest_difficulty(rating)),
+        None => get_difficulty(options.difficulty, &known),
+    };
 
-        let winner  = game.start();
+    let adaptive = (options.adaptive && humans > 0 && ai_players > 0).then(|| Adaptive::new(difficulty));
+
+    if let Some(adaptive) = &adaptive {
+        println!("The AIs will adapt to how you're doing, starting at {}.", adaptive.describe());
+    }
+
+    let screen = Screen::shared(human_names.first().map_or("", |n| n.as_str()));
+    let new_human = |name: &String, seat: usize| {
+        if options.tui {
+            Box::new(TuiPlayer::new(name.clone(), seat, screen.clone())) as Box<dyn Player>
+        } else {
+            let mut human = player::Human::new(name.clone(), hot_seat);
+
+            if options.ascii {
+                human.set_charset(Charset::Ascii);
+            }
+
+            human.set_accessible(options.accessible);
+            human.set_coach(options.coach);
+
+            Box::new(human) as Box<dyn Player>
+        }
+    };
 
-        if winner == human.name() {
+    let new_ai = |profile: Option<&AiProfile>| {
+        let mut ai = match profile {
+            Some(profile) => player::Ai::with_profile(StdRng::from_entropy(), profile.clone()),
+            None => player::Ai::new(StdRng::from_entropy(), difficulty),
+        };
+
+        if let Some(adaptive) = &adaptive {
+            ai.set_adaptive(adaptive.clone());
+        }
+
+        ai
+    };
+
+    let mut players: Vec<Box<dyn Player>> = vec![];
+    let mut seats = vec![];
+
+    if let Some(saved) = &saved {
+        for seat in saved.seats.iter() {
+            if seat.human {
+                players.push(new_human(&seat.name, players.len()));
+            } else {
+                let profile = seat.personality.as_deref().and_then(find_personality);
+
+                if let (Some(personality), None) = (&seat.personality, profile) {
+                    println!("{} was the {personality}, which isn't around any more; they'll play as a {difficulty} AI.", seat.name);
+                }
+
+                let mut ai = new_ai(profile);
+                ai.set_name(&seat.name);
+                players.push(Box::new(ai));
+            }
+        }
+
+        seats = saved.seats.clone();
+    }
+
+    // Alternate AIs and humans, starting with an AI, so people don't sit next to each other when it can be avoided.
+    let mut ais = (0..ai_players).map(|i| new_ai(chosen.get(i).copied()));
+    let mut people = human_names.iter();
+
+    while players.len() < humans + ai_players {
+        if let Some(ai) = ais.next() {
+            let personality = Some(ai.profile().personality.clone()).filter(|p| !p.is_empty());
+
+            if let Some(personality) = &personality {
+                println!("{}'s personality: {personality}.", ai.name());
+            }
+
+            seats.push(Seat { name: ai.name().to_string(), human: false, personality });
+            players.push(Box::new(ai));
+        }
+
+        if let Some(name) = people.next() {
+            seats.push(Seat { name: name.clone(), human: true, personality: None });
+            players.push(new_human(name, players.len()));
+        }
+    }
+
+    let checkpoint: Checkpoint = Arc::new(Mutex::new(None));
+
+    save::set_current(save::CurrentGame { rules, difficulty, seats: seats.clone(), checkpoint: checkpoint.clone() });
+
+    let _ = ctrlc::set_handler(|| {
+        save::autosave();
+        std::process::exit(130);
+    });
+
+    let mut seed = options.seed;
+
+    if spectate {
+        let mut game = GameState::new(players, rules);
+
+        game.set_turn_delay(Duration::ZERO);
+        game.add_observer(Box::new(Spectator::new(delay)));
+        let record = start_recording(&mut game, options.record.as_deref(), seed, rules);
+        #[cfg(feature = "history")]
+        game.add_observer(Box::new(MatchLogger::new(&seats, Some(difficulty), rules, Some(game.seed()))));
+
+        if let Some(winner) = game.start() {
+            println!("{winner} won!");
+        }
+
+        print_recorded(record);
+        return;
+    }
+
+    // Only a lone human's turns are worth reviewing; in hot-seat games nobody would know whose review it is.
+    let reviewable = humans == 1 && !hot_seat;
+    let decisions = Arc::new(Mutex::new(vec![]));
+
+    let mut resume = saved.map(|saved| saved.position);
+
+    'games: loop {
+        let resuming = resume.is_some();
+        let mut game = match resume.take() {
+            Some(position) => GameState::from_position(players, rules, position),
+            None => GameState::new(players, rules),
+        };
+
+        game.set_turn_delay(delay);
+        game.set_checkpoint(checkpoint.clone());
+
+        // Only the first game follows --seed; playing the same deal again and again would get old. A resumed game
+        // didn't start from a seed, so it can't be recorded.
+        let record = if resuming { None } else { start_recording(&mut game, options.record.as_deref(), seed.take(), rules) };
+
+        // Adaptive games count towards whichever difficulty the AIs are closest to.
+        let rated = (ai_players > 0).then(|| adaptive.as_ref().map_or(difficulty, Adaptive::nearest));
+
+        game.add_observer(Box::new(ProfileTracker::new(&seats, rated)));
+        #[cfg(feature = "history")]
+        game.add_observer(Box::new(MatchLogger::new(&seats, rated, rules, (!resuming).then(|| game.seed()))));
+
+        if let Some(adaptive) = &adaptive {
+            game.add_observer(Box::new(Adjuster::new(adaptive.clone(), &seats)));
+        }
+
+        let before = Profiles::load().unwrap_or_default();
+
+        if let (true, Some(seat)) = (reviewable, seats.iter().position(|s| s.human)) {
+            game.add_observer(Box::new(Recorder::new(seat, rules, decisions.clone())));
+        }
+
+        let run = |game: &mut GameState| {
+            let winner = if resuming { game.resume() } else { game.start() };
+            winner.expect("nothing stops a game here, so it always has a winner")
+        };
+
+        if resuming {
+            println!("Picking up the saved game where it left off.");
+        }
+
+        let winner = if options.tui {
+            game.add_observer(Box::new(TuiView(screen.clone())));
+
+            let session = Session::start().expect("the terminal does not support the full-screen UI");
+            let winner = run(&mut game);
+
+            // Leave the final table up for a moment before returning to the prompt.
+            std::thread::sleep(std::time::Duration::from_millis(1500));
+            drop(session);
+
+            winner
+        } else {
+            if options.accessible {
+                game.add_observer(Box::new(Announcer));
+            } else {
+                game.add_observer(Box::new(Transcript));
+            }
+
+            run(&mut game)
+        };
+
+        players = game.into_players();
+
+        if !hot_seat && human_names.contains(&winner) {
             println!("You won!");
         } else {
             println!("{winner} won!");
         }
 
-        std::thread::sleep(std::time::Duration::from_millis(1500));
+        profile::print_rating_changes(&before, &human_names);
 
-        let mut input = String::new();
+        if let Some(adaptive) = &adaptive {
+            let rate = adaptive.win_rate().unwrap_or_default();
+            println!("Win rate lately: {:.0}%. The AIs' level for the next game: {}.", rate * 100.0, adaptive.describe());
+        }
 
-        println!("[P]lay again or [Q]uit?");
+        print_recorded(record);
+        std::thread::sleep(pause);
 
-        std::io::stdin().read_line(&mut input).unwrap();
+        loop {
+            let mut input = String::new();
 
-        if matches!(input.trim().to_lowercase().as_str(), "q" | "quit") {
-            break;
+            match (options.accessible, reviewable) {
+                (true, true) => println!("Type q to quit, r to review the game, or press Enter to play again."),
+                (true, false) => println!("Type q to quit, or press Enter to play again."),
+                (false, true) => println!("[P]lay again, [R]eview or [Q]uit?"),
+                (false, false) => println!("[P]lay again or [Q]uit?"),
+            }
+
+            std::io::stdin().read_line(&mut input).unwrap();
+
+            match input.trim().to_lowercase().as_str() {
+                "q" | "quit" => break 'games,
+                "r" | "review" if reviewable => review::print(&decisions.lock().unwrap()),
+                _ => break,
+            }
         }
 
-        print!("\x1B[2J\x1B[1;1H");
+        if !options.accessible {
+            print!("\x1B[2J\x1B[1;1H");
+        }
     }
 }
 
-fn get_difficulty() -> AIDifficulty {
-    if std::env::args().len() > 1 {
-        let arg = std::env::args().nth(1).unwrap();
+const MAX_PLAYERS: usize = 8;
 
-        return match arg.to_lowercase().as_str() {
-            "-e" | "--easy" => AIDifficulty::Easy,
-            "-m" | "--medium" => AIDifficulty::Medium,
-            "-h" | "--hard" => AIDifficulty::Hard,
-            _ => {
-                println!("Invalid difficulty. Defaulting to Medium.");
-                AIDifficulty::Medium
-            }
+/// Seeds the game and, if there's a `dir` to record to, adds a writer for it. Returns where the record goes.
+fn start_recording(game: &mut GameState, dir: Option<&Path>, seed: Option<u64>, rules: RuleSet) -> Option<PathBuf> {
+    if let Some(seed) = seed {
+        game.set_seed(seed);
+    }
+
+    let path = dir?.join(format!("game-{}.jsonl", game.seed()));
+
+    match RecordWriter::create(&path, game.seed(), rules) {
+        Ok(writer) => {
+            game.add_observer(Box::new(writer));
+            Some(path)
+        }
+        Err(e) => {
+            println!("Could not record the game to {}: {e}", path.display());
+            None
         }
     }
+}
+
+fn print_recorded(record: Option<PathBuf>) {
+    if let Some(path) = record {
+        println!("The game was recorded to {}.", path.display());
+    }
+}
+
+fn get_difficulty(difficulty: Option<AIDifficulty>, profiles: &Profiles) -> AIDifficulty {
+    if let Some(difficulty) = difficulty {
+        return difficulty;
+    }
 
     let mut input = String::new();
 
     loop {
-        println!("Choose a difficulty: [E]asy, [M]edium, [H]ard");
+        println!(
+            "Choose a difficulty: [E]asy ({:.0}), [M]edium ({:.0}), [H]ard ({:.0})",
+            profiles.ai_rating(AIDifficulty::Easy),
+            profiles.ai_rating(AIDifficulty::Medium),
+            profiles.ai_rating(AIDifficulty::Hard)
+        );
 
         std::io::stdin().read_line(&mut input).unwrap();
 
@@ -0,0 +1,217 @@
+//! AI personalities: the numbers behind how an AI picks its cards, read from TOML. The three difficulties are
+//! personalities too, just unnamed ones; see `ai/personalities.toml` for the format and the named ones that come
+//! built in.
+
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::player::AIDifficulty;
+
+const BUILT_IN: &str = include_str!("../ai/personalities.toml");
+
+/// The kinds of action card an AI weighs against each other, in the order of [`AiProfile::weights`].
+pub const CARD_KINDS: [&str; 5] = ["draw_two", "skip", "draw_four", "reverse", "wild"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiProfile {
+    /// What the personality is called, e.g. "aggressor"; empty for the plain difficulties.
+    pub personality: String,
+    /// Names an AI with this personality goes by. If there are none, it picks one of the usual names.
+    pub names: Vec<String>,
+    /// Which way of picking a card it builds on.
+    pub strategy: AIDifficulty,
+    pub aggression: f64,
+    pub stacking: f64,
+    pub color_hoarding: f64,
+    pub bluff_draw: f64,
+    pub wild_saving: f64,
+    /// How much it prefers each kind of action card, in the order of [`CARD_KINDS`].
+    pub weights: [f64; 5],
+}
+
+impl AiProfile {
+    /// How each difficulty has always played.
+    pub fn for_difficulty(difficulty: AIDifficulty) -> AiProfile {
+        let (bluff_draw, weights) = match difficulty {
+            AIDifficulty::Easy => (0.06, [0.3, 0.2, 0.05, 0.2, 0.25]),
+            AIDifficulty::Medium => (0.04, [0.3, 0.2, 0.05, 0.2, 0.25]),
+            AIDifficulty::Hard => (0.03, [0.4, 0.1, 0.35, 0.05, 0.15]),
+        };
+
+        AiProfile {
+            personality: String::new(),
+            names: vec![],
+            strategy: difficulty,
+            aggression: 1.0,
+            stacking: 1.0,
+            color_hoarding: 1.0,
+            bluff_draw,
+            wild_saving: 0.0,
+            weights,
+        }
+    }
+
+    fn from_toml(personality: &str, table: &Table) -> Result<AiProfile, String> {
+        let strategy = match table.get("strategy") {
+            Some(Value::String(strategy)) => strategy.parse()?,
+            Some(other) => return Err(format!("the strategy should be easy, medium or hard, not {other}")),
+            None => AIDifficulty::Medium,
+        };
+
+        let mut profile = AiProfile::for_difficulty(strategy);
+        profile.personality = personality.to_string();
+
+        if let Some(names) = table.get("names") {
+            profile.names = names
+                .as_array()
+                .and_then(|names| names.iter().map(|n| n.as_str().map(str::to_string)).collect())
+                .ok_or("the names should be a list of strings")?;
+        }
+
+        for (key, trait_) in [
+            ("aggression", &mut profile.aggression),
+            ("stacking", &mut profile.stacking),
+            ("color_hoarding", &mut profile.color_hoarding),
+            ("bluff_draw", &mut profile.bluff_draw),
+            ("wild_saving", &mut profile.wild_saving),
+        ] {
+            if let Some(value) = table.get(key) {
+                *trait_ = number(value).filter(|n| (0.0..=1.0).contains(n)).ok_or_else(|| format!("{key} should be between 0 and 1"))?;
+            }
+        }
+
+        if let Some(weights) = table.get("weights") {
+            let weights = weights.as_table().ok_or("the weights should be a table")?;
+
+            for (kind, weight) in CARD_KINDS.iter().zip(profile.weights.iter_mut()) {
+                if let Some(value) = weights.get(*kind) {
+                    *weight = number(value).filter(|n| *n >= 0.0).ok_or_else(|| format!("the {kind} weight can't be negative"))?;
+                }
+            }
+
+            if profile.weights.iter().all(|w| *w == 0.0) {
+                return Err("at least one weight has to be above 0".to_string());
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// A personality file holding just this profile.
+    pub fn to_toml(&self) -> String {
+        let number = |n: f64| Value::Float((n * 1000.0).round() / 1000.0);
+        let mut table = Table::new();
+
+        if !self.names.is_empty() {
+            table.insert("names".to_string(), Value::Array(self.names.iter().cloned().map(Value::String).collect()));
+        }
+
+        table.insert("strategy".to_string(), Value::String(self.strategy.to_string().to_lowercase()));
+
+        for (key, value) in [
+            ("aggression", self.aggression),
+            ("stacking", self.stacking),
+            ("color_hoarding", self.color_hoarding),
+            ("bluff_draw", self.bluff_draw),
+            ("wild_saving", self.wild_saving),
+        ] {
+            table.insert(key.to_string(), number(value));
+        }
+
+        let weights = CARD_KINDS.iter().zip(self.weights).map(|(kind, weight)| (kind.to_string(), number(weight))).collect();
+        table.insert("weights".to_string(), Value::Table(weights));
+
+        let mut document = Table::new();
+        document.insert(self.personality.clone(), Value::Table(table));
+        document.to_string()
+    }
+}
+
+/// TOML integers are a different type, but `1` should do as well as `1.0`.
+fn number(value: &Value) -> Option<f64> {
+    value.as_float().or_else(|| value.as_integer().map(|n| n as f64))
+}
+
+/// Reads every personality in a TOML document.
+pub fn parse(text: &str) -> Result<Vec<AiProfile>, String> {
+    let table = text.parse::<Table>().map_err(|e| e.to_string())?;
+
+    table
+        .iter()
+        .map(|(name, value)| {
+            let table = value.as_table().ok_or_else(|| format!("{name} should be a table"))?;
+            AiProfile::from_toml(name, table).map_err(|e| format!("{name}: {e}"))
+        })
+        .collect()
+}
+
+/// The built-in personalities, followed by any in `path`. A personality in the file replaces a built-in one of the
+/// same name.
+pub fn available(path: Option<&Path>) -> Result<Vec<AiProfile>, String> {
+    let mut profiles = parse(BUILT_IN).expect("the built-in personalities are valid");
+
+    if let Some(path) = path {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+
+        for profile in parse(&text).map_err(|e| format!("{} is not a valid personality file: {e}", path.display()))? {
+            profiles.retain(|p| p.personality != profile.personality);
+            profiles.push(profile);
+        }
+    }
+
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_profiles_round_trip_through_toml() {
+        for difficulty in [AIDifficulty::Easy, AIDifficulty::Medium, AIDifficulty::Hard] {
+            let profile = AiProfile::for_difficulty(difficulty);
+
+            assert_eq!(parse(&profile.to_toml()), Ok(vec![profile]));
+        }
+
+        for profile in available(None).unwrap() {
+            assert_eq!(parse(&profile.to_toml()), Ok(vec![profile]));
+        }
+    }
+
+    #[test]
+    fn the_shipped_personalities_load() {
+        let profiles = available(None).unwrap();
+
+        assert!(!profiles.is_empty());
+        assert!(profiles.iter().all(|p| !p.personality.is_empty() && !p.names.is_empty()), "{profiles:?}");
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        for (text, error) in [
+            ("[hothead]\naggression = 1.5", "hothead: aggression should be between 0 and 1"),
+            ("[hothead]\nbluff_draw = -0.1", "hothead: bluff_draw should be between 0 and 1"),
+            ("[hothead]\nweights = { skip = -1 }", "hothead: the skip weight can't be negative"),
+            (
+                "[hothead]\nweights = { draw_two = 0, skip = 0, draw_four = 0, reverse = 0, wild = 0 }",
+                "hothead: at least one weight has to be above 0",
+            ),
+            ("[hothead]\nstrategy = \"brutal\"", "hothead: "),
+        ] {
+            match parse(text) {
+                Ok(profiles) => panic!("{text} gave {profiles:?}"),
+                Err(e) => assert!(e.starts_with(error), "{e}"),
+            }
+        }
+    }
+
+    #[test]
+    fn whole_numbers_count_as_numbers() {
+        let profiles = parse("[calm]\naggression = 0\nweights = { wild = 1 }").unwrap();
+
+        assert_eq!(profiles[0].aggression, 0.0);
+        assert_eq!(profiles[0].weights[4], 1.0);
+    }
+}
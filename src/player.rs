@@ -1,149 +1,313 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::io::stdin;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::time::Duration;
+use crate::adaptive::Adaptive;
+use crate::art;
+use crate::art::Charset;
 use crate::card::{Card, CardColor};
+use crate::coach;
+use crate::command::{CardRef, Command, HELP};
+use crate::hand;
+use crate::personality::AiProfile;
+use crate::hand::HandSort;
+use crate::save;
 use rand::{Rng, RngCore};
 use rand::distributions::WeightedIndex;
-use crate::game::{GameState, Turn, TurnResult};
+use crate::game::{GameEvent, GameState, Turn, TurnResult};
 
 pub struct Human {
     name: String,
+    /// Several people share this terminal, so hands are only shown once the right person has the keyboard.
+    hot_seat: bool,
+    players: Vec<String>,
+    /// Public events since this player's last turn, replayed after the pass screen clears them away.
+    missed: Vec<String>,
+    /// Cards handed to us outside our turn; revealed once we have the keyboard again.
+    pending_draws: Vec<Card>,
+    drawing: bool,
+    /// Public events this game, for the history command.
+    history: Vec<String>,
+    charset: Charset,
+    sort: HandSort,
+    /// Show a hint at the start of every turn.
+    coach: bool,
+    /// Short plain sentences for screen readers instead of card art and screen clearing.
+    accessible: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AIDifficulty {
     Easy,
     Medium,
     Hard,
 }
 
-pub struct Ai<'a, R: RngCore> {
-    ran: &'a mut R,
+impl Display for AIDifficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AIDifficulty::Easy => write!(f, "Easy"),
+            AIDifficulty::Medium => write!(f, "Medium"),
+            AIDifficulty::Hard => write!(f, "Hard"),
+        }
+    }
+}
+
+impl FromStr for AIDifficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "e" | "easy" => Ok(AIDifficulty::Easy),
+            "m" | "medium" => Ok(AIDifficulty::Medium),
+            "h" | "hard" => Ok(AIDifficulty::Hard),
+            _ => Err(format!("{s} is not a valid difficulty")),
+        }
+    }
+}
+
+pub struct Ai<R: RngCore> {
+    ran: R,
     name: String,
-    difficulty: AIDifficulty,
+    profile: AiProfile,
+    /// If set, the difficulty is picked afresh every turn from the adaptive level.
+    adaptive: Option<Adaptive>,
 }
 
-pub trait Player {
+pub trait Player: Send {
     fn name(&self) -> &str;
     fn execute_turn(&mut self, turn: &Turn) -> TurnResult;
     fn observe_turn(&self, other: &dyn Player, card: &Card);
-    fn observe_turn_skip(&self, observed_cards: Option<Vec<&Card>>);
+    fn observe_turn_skip(&mut self, observed_cards: Option<Vec<&Card>>);
+    fn observe_event(&mut self, _event: &GameEvent) {}
 }
+
+/// A player that decides in the background, such as one on the other end of a socket. The engine starts its turn,
+/// then polls for the decision for as long as it's willing to wait; see [`crate::deadline::Deadline`].
+pub trait AsyncPlayer: Send {
+    fn name(&self) -> &str;
+    fn start_turn(&mut self, turn: &Turn);
+    /// Waits up to `timeout` (forever if `None`) for the decision on the turn last started.
+    fn poll_turn(&mut self, timeout: Option<Duration>) -> Option<TurnResult>;
+    /// Called when the engine stops waiting; a decision that arrives afterwards is discarded.
+    fn cancel_turn(&mut self);
+    fn observe_turn_skip(&mut self, observed_cards: Option<Vec<&Card>>);
+    fn observe_event(&mut self, _event: &GameEvent) {}
+}
+
 pub trait AiPlayer: Player {}
 pub trait HumanPlayer: Player {}
 
-const AI_NAMES: [&str; 20] = [
+const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+
+pub const AI_NAMES: [&str; 20] = [
     "Yukii", "Kurisu", "Mayuri", "Makise", "Misa", "Rin", "Miku", "Shinobu", "Shiro", "Rem",
     "Asuna", "Kirito", "Kazuto", "Shana", "Yoshino", "Yui", "Touka", "Rize", "Mikasa", "Levi",
 ];
 
 impl Human {
-    pub fn new(name: String) -> Human {
+    pub fn new(name: String, hot_seat: bool) -> Human {
         Human {
             name,
+            hot_seat,
+            players: vec![],
+            missed: vec![],
+            pending_draws: vec![],
+            drawing: false,
+            history: vec![],
+            charset: Charset::detect(),
+            sort: HandSort::Color,
+            coach: false,
+            accessible: false,
         }
     }
 
-fn get_action(&mut self, turn: &Turn) -> Option<TurnResult> {
-    let mut input = String::new();
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset = charset;
+    }
 
-    loop {
-        stdin().read_line(&mut input).unwrap();
-        let cur_input = input.trim().to_lowercase();
+    pub fn set_coach(&mut self, coach: bool) {
+        self.coach = coach;
+    }
 
-        match cur_input.as_str() {
-            "play" => {
-                break self.get_card(turn)
-            },
-            "draw" => {
-                return Some(TurnResult::Drew);
-            },
-            _ => {
-                input.clear();
-                println!("Invalid input. Please try again.");
-            }
+    pub fn set_accessible(&mut self, accessible: bool) {
+        self.accessible = accessible;
+    }
+
+    fn clear_screen(&self) {
+        if !self.accessible {
+            print!("\x1B[2J\x1B[1;1H");
         }
     }
-}
 
-    fn get_card(&mut self, turn: &Turn) -> Option<TurnResult> {
+    fn wait_for_enter() {
         let mut input = String::new();
+        stdin().read_line(&mut input).unwrap();
+    }
+
+    /// Hides the previous player's screen and waits until this player has the keyboard.
+    fn pass_keyboard(&mut self) {
+        self.clear_screen();
+        println!("Pass the keyboard to {}, then press Enter.", self.name);
+        Human::wait_for_enter();
+        self.clear_screen();
 
-        println!("You can play the following cards:");
+        if !self.missed.is_empty() {
+            println!("Since your last turn:");
 
-        for (i, card) in turn.playable_hand.iter().enumerate() {
-            let card = GameState::get_colorized_card_name(*card);
-            println!("{i}: {card}");
+            for line in self.missed.drain(..) {
+                println!("  {line}");
+            }
         }
 
-        println!("Enter a number to select a card, or type 'back' to go back to the decision screen.");
+        if !self.pending_draws.is_empty() {
+            let cards = self.pending_draws.drain(..).map(GameState::get_colorized_card_name).collect::<Vec<String>>();
+            println!("You were handed {}.", cards.join(", "));
+        }
+    }
 
-        let index = loop {
-            stdin().read_line(&mut input).unwrap();
-            let cur_input = input.trim().to_lowercase();
+    /// The whole turn in the same order every time: whose turn, the top card, any pending draw, then the hand.
+    fn announce_turn(&self, turn: &Turn) {
+        println!("Your turn, {}.", self.name);
+        println!("The top card is {}.", turn.last_card);
 
-            if cur_input == "back" {
-                return None;
-            }
+        if turn.to_draw > 0 {
+            println!("You must draw {} cards unless you stack.", turn.to_draw);
+        }
 
-            // TODO: Clear?
-            match cur_input.parse::<usize>() {
-                Ok(index) => {
-                    if index >= turn.playable_hand.len() {
-                        input.clear();
-                        println!("Invalid input. Please try again.");
-                        continue;
-                    }
+        self.show_hand(turn);
+        println!("{} of them can be played.", turn.playable_hand.len());
+    }
 
-                    break index;
-                },
-                Err(_) => {
-                    input.clear();
-                    println!("Invalid input. Please try again.");
-                    continue;
-                }
-            }
-        };
+    fn player_name(&self, seat: usize) -> &str {
+        self.players.get(seat).map_or("Someone", |name| name.as_str())
+    }
 
-        let mut card = turn.playable_hand[index];
+    /// The hand in the order it's shown, which is also the order cards are numbered in.
+    fn sorted_hand(&self, turn: &Turn) -> Vec<Card> {
+        hand::sort_hand(&turn.full_hand, &turn.playable_hand, self.sort)
+    }
 
-        match card {
-            Card::Wild { .. } => {
-                let color = Human::get_color();
+    fn show_hand(&self, turn: &Turn) {
+        let hand = self.sorted_hand(turn);
 
-                color.map(|color| TurnResult::Played(*card.with_color(color).unwrap()))
-            },
-            Card::DrawFour { .. } => {
-                let color = Human::get_color();
+        if self.accessible {
+            let hand = hand
+                .iter()
+                .enumerate()
+                .map(|(i, c)| if turn.playable_hand.contains(c) { format!("{} {c} (playable)", i + 1) } else { format!("{} {c}", i + 1) })
+                .collect::<Vec<String>>();
 
-                color.map(|color| TurnResult::Played(*card.with_color(color).unwrap()))
-            },
-            _ => {
-                Some(TurnResult::Played(card))
-            }
+            println!("Your hand has {} cards: {}.", hand.len(), hand.join(", "));
+        } else {
+            println!("Your hand (* can be played):");
+            println!("{}", art::render_hand(&hand, &turn.playable_hand, art::terminal_width(), self.charset));
         }
     }
 
-    fn get_color() -> Option<CardColor> {
+    /// Reads commands until one of them ends the turn.
+    fn read_command(&mut self, turn: &Turn) -> TurnResult {
         let mut input = String::new();
-        loop {
-            println!("Enter a color to choose, or type 'back' to go back to the decision screen.");
 
-            stdin().read_line(&mut input).unwrap();
-            let cur_input = input.trim().to_lowercase();
+        // A screen reader reads the prompt once, so it should say every way to answer it.
+        if self.accessible {
+            println!(
+                "Type p and a card's number, such as p 3, or p and a card, such as p r5 or p w blue, to play it. Type \
+                 draw to draw, why and a card to hear whether it can be played, or help for every command."
+            );
+        } else {
+            println!("Type a command, such as p 1 or d. Type help for the full list.");
+        }
+
+        loop {
+            input.clear();
 
-            if cur_input == "back" {
-                return None;
+            if stdin().read_line(&mut input).unwrap() == 0 {
+                std::process::exit(0);
             }
 
-            if let Ok(color) = CardColor::from_str(&cur_input) {
-                return Some(color);
-            } else {
-                println!("{cur_input} is not a valid color!");
-                continue;
+            let command = match input.parse::<Command>() {
+                Ok(command) => command,
+                Err(e) => {
+                    println!("{e}");
+                    continue;
+                }
+            };
+
+            match command {
+                Command::Play { card, color } => match self.play(turn, card, color) {
+                    Ok(result) => return result,
+                    Err(e) => println!("{e}"),
+                },
+                Command::Draw => return TurnResult::Drew,
+                Command::Hand => self.show_hand(turn),
+                Command::Hint => println!("Hint: {}", coach::hint(turn)),
+                Command::Coach => {
+                    self.coach = !self.coach;
+                    println!("Hints every turn are now {}.", if self.coach { "on" } else { "off" });
+                }
+                Command::Why(card) => {
+                    let card = match card {
+                        CardRef::Index(index) => self.sorted_hand(turn).get(index - 1).copied(),
+                        CardRef::Card(card) => Some(card),
+                    };
+
+                    match card.map(|card| (card, turn.rules.check_play(&card, &turn.last_card, turn.to_draw))) {
+                        None => println!("You only have {} cards.", turn.full_hand.len()),
+                        Some((card, Ok(allowed))) => println!("{card} can be played: {allowed}."),
+                        Some((card, Err(blocked))) => println!("{card} can't be played: {blocked}."),
+                    }
+                }
+                Command::Sort(sort) => {
+                    self.sort = sort;
+                    self.show_hand(turn);
+                }
+                Command::History => {
+                    for line in self.history.iter().skip(self.history.len().saturating_sub(10)) {
+                        println!("  {line}");
+                    }
+                }
+                Command::Save => match save::save(&save::default_path()) {
+                    Ok(()) => println!("Saved. Start with load to pick up again from the start of this turn."),
+                    Err(e) => println!("{e}"),
+                },
+                Command::Help => println!("{HELP}"),
+                Command::Quit => std::process::exit(0),
             }
         }
     }
+
+    /// Checks a play against the hand and the table, explaining what's wrong with it if anything.
+    fn play(&self, turn: &Turn, card: CardRef, color: Option<CardColor>) -> Result<TurnResult, String> {
+        // A wild can be named with its color, as in `p w:r`.
+        let color = match card {
+            CardRef::Card(card @ (Card::Wild { .. } | Card::DrawFour { .. })) => color.or(card.color()),
+            _ => color,
+        };
+
+        let mut card = match card {
+            CardRef::Index(index) => *self.sorted_hand(turn)
+                .get(index - 1)
+                .ok_or_else(|| format!("You only have {} cards.", turn.full_hand.len()))?,
+            CardRef::Card(card) => *turn.full_hand
+                .iter()
+                .find(|c| **c == card)
+                .ok_or_else(|| format!("You don't have a {card}."))?,
+        };
+
+        if let Err(blocked) = turn.rules.check_play(&card, &turn.last_card, turn.to_draw) {
+            return Err(format!("You can't play {card}: {blocked}."));
+        }
+
+        match (matches!(card, Card::Wild { .. } | Card::DrawFour { .. }), color) {
+            (true, Some(color)) => Ok(TurnResult::Played(*card.with_color(color).unwrap())),
+            (true, None) => Err("Wild cards need a color too, e.g. p w red or p wd4 b.".to_string()),
+            (false, Some(_)) => Err(format!("Only wild cards take a color; {card} is already {}.", card.color().unwrap())),
+            (false, None) => Ok(TurnResult::Played(card)),
+        }
+    }
 }
 
 impl Player for Human {
@@ -152,37 +316,53 @@ impl Player for Human {
     }
 
     fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
-        println!("Its your turn.");
+        if self.hot_seat {
+            self.pass_keyboard();
+        }
 
-        let can_play = !turn.playable_hand.is_empty();
+        if self.accessible {
+            self.announce_turn(turn);
+        } else {
+            println!("The top card is:");
+            println!("{}", art::render_card(turn.last_card, self.charset));
+            self.show_hand(turn);
+            println!("Its your turn.");
+        }
 
-        loop {
-            if can_play {
-                println!("What would you like to do? [Play], [Draw]");
-            }
-            else {
-                println!("You have no cards to play. You must draw.");
+        let result = if turn.playable_hand.is_empty() {
+            println!("You have no cards to play. You must draw.");
+            TurnResult::Drew
+        } else {
+            if self.coach {
+                println!("Hint: {}", coach::hint(turn));
             }
 
-            if can_play {
-                let action = self.get_action(turn);
+            self.read_command(turn)
+        };
 
-                if let Some(action) = action {
-                    break action;
-                }
-            }
-            else {
-                break TurnResult::Drew
+        if self.hot_seat {
+            match result {
+                TurnResult::Drew => self.drawing = true,
+                TurnResult::Played(_) => self.clear_screen(),
             }
         }
 
+        result
     }
 
     fn observe_turn(&self, other: &dyn Player, card: &Card) {
         println!("{} played a {}.", other.name(), card);
     }
 
-    fn observe_turn_skip(&self, observed_cards: Option<Vec<&Card>>) {
+    fn observe_turn_skip(&mut self, observed_cards: Option<Vec<&Card>>) {
+        if self.hot_seat && !self.drawing {
+            if let Some(observed_cards) = observed_cards {
+                self.pending_draws.extend(observed_cards);
+            }
+
+            return;
+        }
+
         if let Some(observed_cards) = observed_cards {
             if observed_cards.len() == 1 {
                 println!("You drew a {}.", GameState::get_colorized_card_name(*observed_cards[0]));
@@ -197,20 +377,102 @@ impl Player for Human {
         else {
             println!("You have been skipped!");
         }
+
+        if self.drawing {
+            self.drawing = false;
+
+            println!("Press Enter to end your turn.");
+            Human::wait_for_enter();
+            self.clear_screen();
+        }
+    }
+
+    fn observe_event(&mut self, event: &GameEvent) {
+        let line = match event {
+            GameEvent::Started { players, .. } => {
+                self.players = players.clone();
+                self.history.clear();
+                return;
+            }
+            GameEvent::Played { player, card } => format!("{} played {}", self.player_name(*player), GameState::get_colorized_card_name(*card)),
+            GameEvent::Drew { player, count } => format!("{} drew {count} card(s)", self.player_name(*player)),
+            GameEvent::Skipped { player } => format!("{}'s turn was skipped", self.player_name(*player)),
+            GameEvent::TurnStarted { .. } | GameEvent::Won { .. } => return,
+        };
+
+        self.history.push(line.clone());
+
+        let own_turn = matches!(event, GameEvent::Played { player, .. } | GameEvent::Drew { player, .. } if self.player_name(*player) == self.name);
+
+        if self.hot_seat && !own_turn {
+            self.missed.push(line);
+        }
     }
 }
 
 impl HumanPlayer for Human {}
 
-impl<'a, R> Ai<'a, R> where R: RngCore {
+impl<R> Ai<R> where R: RngCore {
+
+    pub fn new(ran: R, difficulty: AIDifficulty) -> Ai<R> {
+        Ai::with_profile(ran, AiProfile::for_difficulty(difficulty))
+    }
 
-    pub fn new(ran: &'a mut R, difficulty: AIDifficulty) -> Ai<'a, R> {
-        let name = AI_NAMES[ran.gen_range(0..AI_NAMES.len())].to_string();
+    /// An AI with a personality, going by one of its names.
+    pub fn with_profile(mut ran: R, profile: AiProfile) -> Ai<R> {
+        let name = match profile.names.is_empty() {
+            true => AI_NAMES[ran.gen_range(0..AI_NAMES.len())].to_string(),
+            false => profile.names[ran.gen_range(0..profile.names.len())].clone(),
+        };
 
         Ai {
             ran,
             name,
-            difficulty
+            profile,
+            adaptive: None,
+        }
+    }
+
+    pub fn profile(&self) -> &AiProfile {
+        &self.profile
+    }
+
+    pub fn set_adaptive(&mut self, adaptive: Adaptive) {
+        self.adaptive = Some(adaptive);
+    }
+
+    /// Replaces the randomly picked name, e.g. to bring back a saved game's seats as they were.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// What this AI would play, without the occasional deliberate draw. `turn` must have something playable.
+    pub fn suggest(&mut self, turn: &Turn) -> TurnResult {
+        let is_number = |c: &Card| matches!(c, Card::Numeric { .. });
+        let is_wild = |c: &Card| matches!(c, Card::Wild { .. } | Card::DrawFour { .. });
+        let mut turn = turn.clone();
+
+        // Narrow down what the strategy gets to choose from, as long as that leaves something to play.
+        if turn.playable_hand.iter().any(is_number) && !self.ran.gen_bool(self.profile.aggression) {
+            turn.playable_hand.retain(is_number);
+        }
+
+        if turn.playable_hand.iter().any(|c| !is_wild(c)) && self.ran.gen_bool(self.profile.wild_saving) {
+            turn.playable_hand.retain(|c| !is_wild(c));
+        }
+
+        let result = match self.profile.strategy {
+            AIDifficulty::Easy => self.easy(&turn),
+            AIDifficulty::Medium => self.medium(&turn),
+            AIDifficulty::Hard => self.hard(&turn),
+        };
+
+        match result {
+            TurnResult::Played(mut card) if is_wild(&card) && !self.ran.gen_bool(self.profile.color_hoarding) => {
+                card.with_color(COLORS[self.ran.gen_range(0..COLORS.len())]);
+                TurnResult::Played(card)
+            }
+            result => result,
         }
     }
 
@@ -220,7 +482,7 @@ impl<'a, R> Ai<'a, R> where R: RngCore {
             .iter()
             .filter_map(|c| c.color())
             .collect::<Vec<CardColor>>()
-            .group_by(|c, n| c == n)
+            .chunk_by(|c, n| c == n)
             .max_by_key(|item| item.len())
             .map_or(CardColor::Red, |color| color[0])
     }
@@ -253,15 +515,15 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             .iter()
             .filter_map(|c| c.color())
             .collect::<Vec<CardColor>>()
-            .group_by(|c, n| c == n)
+            .chunk_by(|c, n| c == n)
             .max_by_key(|item| item.len())
             .map_or(CardColor::Red, |color| color[0]);
 
-        let weights = vec![0.3, 0.2, 0.05, 0.2, 0.25];
+        let weights = self.profile.weights;
         let card_preferences = [Card::DrawTwo { color: CardColor::Red }, Card::Skip { color: CardColor::Red }, Card::DrawFour { color: None }, Card::Reverse { color: CardColor::Red }, Card::Wild { color: None }];
 
-        let weight_idx = &WeightedIndex::new(&weights).unwrap();
-        let mut weight_iter = self.ran.sample_iter(weight_idx);
+        let weight_idx = &WeightedIndex::new(weights).unwrap();
+        let mut weight_iter = (&mut self.ran).sample_iter(weight_idx);
 
         let mut picked_card: Card = turn.playable_hand[0];
 
@@ -269,16 +531,13 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             for _ in 0..=10 {
                 let selection = weight_iter.next().unwrap(); // Safe; method is guaranteed to return a value.
 
-                let mut card = card_preferences[selection];
+                let card = card_preferences[selection];
 
+                // By kind only: `card` is a placeholder, and recoloring it to match used to unwrap the None that
+                // `with_color` gives for anything but a wild, panicking whenever a colored card was playable.
                 let of_type = turn.playable_hand
                     .iter()
-                    .find
-                    (
-                        |c|
-                            if c.color().is_none() { **c == card }
-                            else { card.with_color(c.color().unwrap()).unwrap() == *c }
-                    );
+                    .find(|c| c.is_equivalent(&card));
 
                 if let Some(card) = of_type {
                     picked_card = *card;
@@ -313,7 +572,7 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
         let should_stack = turn.to_draw > 0 && matches!(turn.last_card, Card::DrawTwo { .. } | Card::DrawFour { .. });
 
         if should_stack {
-            let preferred_color = Self::get_preferable_color(turn.full_hand, last_color);
+            let preferred_color = Self::get_preferable_color(&turn.full_hand, last_color);
 
             if matches!(turn.last_card, Card::DrawFour { .. })
             {
@@ -339,7 +598,7 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             .filter_map(|c| c.color())
             .filter(|c| *c != last_color)
             .collect::<Vec<CardColor>>()
-            .group_by(|c, n| c == n)
+            .chunk_by(|c, n| c == n)
             .fold((usize::MAX, Vec::<&[CardColor]>::new()), |(max, mut list), acc_list| {
                 if acc_list.len() > max {
                     let index = list.iter().position(|c| c.len() == acc_list.len()).unwrap();
@@ -371,20 +630,23 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             .collect::<Vec<Card>>();
 
 
-        let plan_to_change = self.ran.gen_range(0..=100) % std::cmp::max(50 - (turn.full_hand.len() * 2), 1) == 0;
+        // Saturating: a hand of more than 25 cards used to underflow here and panic.
+        let plan_to_change = self.ran.gen_range(0..=100) % std::cmp::max(50_usize.saturating_sub(turn.full_hand.len() * 2), 1) == 0;
 
-        let weights = vec![0.4, 0.1, 0.35, 0.05, 0.15];
+        let weights = self.profile.weights;
 
         let card_types = [Card::DrawTwo { color: CardColor::Red }, Card::Skip { color: CardColor::Red }, Card::DrawFour { color: None }, Card::Reverse { color: CardColor::Red }, Card::Wild { color: None }];
 
-        if can_afford_change && plan_to_change {
+        // A hand that's mostly one color doesn't mean one of its playable cards can change the color; picking from an
+        // empty list here used to panic too.
+        if can_afford_change && plan_to_change && !color_changing_cards.is_empty() {
             let index = self.ran.gen_range(0..color_changing_cards.len());
             let mut picked_card = color_changing_cards[index];
 
-            let preferred_color = Self::get_preferable_color(turn.full_hand, last_color);
+            let preferred_color = Self::get_preferable_color(&turn.full_hand, last_color);
 
-            let weight_idx = &WeightedIndex::new(&weights).unwrap();
-            let mut weight_iter = self.ran.sample_iter(weight_idx);
+            let weight_idx = &WeightedIndex::new(weights).unwrap();
+            let mut weight_iter = (&mut self.ran).sample_iter(weight_idx);
 
             for h in 0..10 {
                 let index = weight_iter.next().unwrap();
@@ -429,7 +691,7 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             // N.B. This may be disadvantageous if the "preferable" color happens to be something we have
             // few of, but the goal of the "hard" AI is to make the game as frustrating for the player as possible
             // even if it comes at the cost of us making a bad move like this.
-            let preferable_color = Self::get_preferable_color(turn.full_hand, last_color);
+            let preferable_color = Self::get_preferable_color(&turn.full_hand, last_color);
 
             if let Card::DrawFour { .. } = card {
                 return TurnResult::Played(Card::DrawFour { color: Some(preferable_color) });
@@ -449,7 +711,7 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
             .filter_map(|c| c.color())
             .filter(|c| *c != last_color)
             .collect::<Vec<CardColor>>()
-            .group_by(|c, n| c == n)
+            .chunk_by(|c, n| c == n)
             .max_by_key(|item| item.len())
             .map_or(last_color, |color| color[0])
 
@@ -457,36 +719,34 @@ fn easy(&mut self, turn: &Turn) -> TurnResult {
 }
 
 
-impl<'a, R> Player for Ai<'a, R> where R : RngCore {
+impl<R> Player for Ai<R> where R : RngCore + Send {
     fn name(&self) -> &str {
         &self.name
     }
 
     fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
-        let draw_or_pick = self.ran.gen_range(0..=100);
+        // A plain difficulty follows the adaptive level wholesale; a personality keeps its quirks and only changes
+        // how it picks a card.
+        if let Some(adaptive) = &self.adaptive {
+            let difficulty = adaptive.pick(&mut self.ran);
 
-        let draw_modifier = match self.difficulty {
-            AIDifficulty::Easy => 0,
-            AIDifficulty::Medium => 10,
-            AIDifficulty::Hard => 15
-        };
+            if self.profile.personality.is_empty() {
+                self.profile = AiProfile::for_difficulty(difficulty);
+            } else {
+                self.profile.strategy = difficulty;
+            }
+        }
 
-        // Math is hard.
-        if draw_or_pick % (20 + draw_modifier) == 0 || turn.playable_hand.is_empty() {
+        if turn.playable_hand.is_empty() || self.ran.gen_bool(self.profile.bluff_draw) {
             return TurnResult::Drew;
         }
 
-        match self.difficulty {
-            AIDifficulty::Easy => {
-                self.easy(turn)
-            },
-            AIDifficulty::Medium => {
-                self.medium(turn)
-            },
-            AIDifficulty::Hard => {
-                self.hard(turn)
-            }
+        // With a draw pending, only cards that stack on it are playable.
+        if turn.to_draw > 0 && !self.ran.gen_bool(self.profile.stacking) {
+            return TurnResult::Drew;
         }
+
+        self.suggest(turn)
     }
 
 
@@ -495,7 +755,7 @@ impl<'a, R> Player for Ai<'a, R> where R : RngCore {
         // Nothing to do here.
     }
 
-    fn observe_turn_skip(&self, _observed_cards: Option<Vec<&Card>>) {
+    fn observe_turn_skip(&mut self, _observed_cards: Option<Vec<&Card>>) {
        // Nothing to do; the game loop handles insertion
     }
 }
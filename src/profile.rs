@@ -0,0 +1,481 @@
+//! Player profiles, kept between runs in the data directory. A profile is created the first time someone with that
+//! name finishes a game, and updated after every game they finish.
+//!
+//! People playing through the server only say who they are, so they're kept in a file of their own rather than
+//! mixed in with the people at this computer.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use serde_json::{json, Value};
+
+use crate::card::{Card, CardColor};
+use crate::game::{GameEvent, GameObserver, Table};
+use crate::player::AIDifficulty;
+use crate::rating;
+use crate::save::{self, Seat};
+
+const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+const DIFFICULTIES: [AIDifficulty; 3] = [AIDifficulty::Easy, AIDifficulty::Medium, AIDifficulty::Hard];
+
+fn path() -> PathBuf {
+    save::data_dir().join("profiles.json")
+}
+
+fn online_path() -> PathBuf {
+    save::data_dir().join("online-profiles.json")
+}
+
+fn slot(difficulty: AIDifficulty) -> usize {
+    match difficulty {
+        AIDifficulty::Easy => 0,
+        AIDifficulty::Medium => 1,
+        AIDifficulty::Hard => 2,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tally {
+    pub games: u32,
+    pub wins: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    /// Games against each AI difficulty, easiest first. Games between people only count towards the totals.
+    pub by_difficulty: [Tally; 3],
+    /// Cards left in hand at the end of every game, added up.
+    pub cards_left: u32,
+    /// The most cards taken in one go from a pending Draw Two or Draw Four.
+    pub longest_stack: u32,
+    /// How often each color was chosen for a wild card, in the order of [`COLORS`].
+    pub wild_colors: [u32; 4],
+    pub rating: f64,
+}
+
+impl Profile {
+    fn new(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            games: 0,
+            wins: 0,
+            by_difficulty: [Tally::default(); 3],
+            cards_left: 0,
+            longest_stack: 0,
+            wild_colors: [0; 4],
+            rating: rating::INITIAL,
+        }
+    }
+
+    pub fn average_cards_left(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.cards_left as f64 / self.games as f64 }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.wins as f64 / self.games as f64 }
+    }
+
+    pub fn favorite_wild_color(&self) -> Option<CardColor> {
+        let (index, count) = self.wild_colors.iter().enumerate().max_by_key(|(i, count)| (**count, usize::MAX - i))?;
+
+        (*count > 0).then_some(COLORS[index])
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "games": self.games,
+            "wins": self.wins,
+            "by_difficulty": DIFFICULTIES
+                .iter()
+                .map(|d| (d.to_string().to_lowercase(), json!({ "games": self.by_difficulty[slot(*d)].games, "wins": self.by_difficulty[slot(*d)].wins })))
+                .collect::<serde_json::Map<String, Value>>(),
+            "cards_left": self.cards_left,
+            "longest_stack": self.longest_stack,
+            "rating": self.rating,
+            "wild_colors": COLORS
+                .iter()
+                .zip(self.wild_colors)
+                .map(|(color, count)| (color.to_string().to_lowercase(), json!(count)))
+                .collect::<serde_json::Map<String, Value>>(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Profile, String> {
+        let name = value["name"].as_str().ok_or("a profile has no name")?;
+        let number = |value: &Value| value.as_u64().unwrap_or(0) as u32;
+        let mut profile = Profile::new(name);
+
+        profile.games = number(&value["games"]);
+        profile.wins = number(&value["wins"]);
+        profile.cards_left = number(&value["cards_left"]);
+        profile.longest_stack = number(&value["longest_stack"]);
+        profile.rating = value["rating"].as_f64().unwrap_or(rating::INITIAL);
+
+        for difficulty in DIFFICULTIES {
+            let tally = &value["by_difficulty"][difficulty.to_string().to_lowercase()];
+            profile.by_difficulty[slot(difficulty)] = Tally { games: number(&tally["games"]), wins: number(&tally["wins"]) };
+        }
+
+        for (i, color) in COLORS.iter().enumerate() {
+            profile.wild_colors[i] = number(&value["wild_colors"][color.to_string().to_lowercase()]);
+        }
+
+        Ok(profile)
+    }
+}
+
+/// Everyone who has played on this computer, plus whoever played last so they don't have to type their name again.
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    pub last: Option<String>,
+    /// Each AI difficulty's rating, easiest first. AIs are rated by difficulty, whatever they're called.
+    pub ai_ratings: [f64; 3],
+}
+
+impl Default for Profiles {
+    fn default() -> Profiles {
+        Profiles { profiles: vec![], last: None, ai_ratings: [rating::INITIAL; 3] }
+    }
+}
+
+impl Profiles {
+    /// Reads the profiles, or starts afresh if there aren't any yet.
+    pub fn load() -> Result<Profiles, String> {
+        Profiles::load_from(&path())
+    }
+
+    /// The profiles of the people who have played through the server.
+    pub fn load_online() -> Result<Profiles, String> {
+        Profiles::load_from(&online_path())
+    }
+
+    fn load_from(path: &Path) -> Result<Profiles, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Profiles::default()),
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{} is not valid JSON: {e}", path.display()))?;
+
+        Ok(Profiles {
+            profiles: value["profiles"]
+                .as_array()
+                .map(|profiles| profiles.iter().map(Profile::from_json).collect::<Result<Vec<Profile>, String>>())
+                .transpose()?
+                .unwrap_or_default(),
+            last: value["last"].as_str().map(str::to_string),
+            ai_ratings: DIFFICULTIES.map(|d| value["ai_ratings"][d.to_string().to_lowercase()].as_f64().unwrap_or(rating::INITIAL)),
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&path())
+    }
+
+    pub fn save_online(&self) -> Result<(), String> {
+        self.save_to(&online_path())
+    }
+
+    /// Writes a whole new file and moves it into place, so the profiles are never left half written.
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        let temporary = path.with_extension("json.tmp");
+        let value = json!({
+            "profiles": self.profiles.iter().map(Profile::to_json).collect::<Vec<Value>>(),
+            "last": self.last,
+            "ai_ratings": DIFFICULTIES
+                .iter()
+                .map(|d| (d.to_string().to_lowercase(), json!(self.ai_ratings[slot(*d)])))
+                .collect::<serde_json::Map<String, Value>>(),
+        });
+
+        std::fs::create_dir_all(save::data_dir())
+            .and_then(|_| std::fs::write(&temporary, format!("{value:#}\n")))
+            .and_then(|_| std::fs::rename(&temporary, path))
+            .map_err(|e| format!("Could not save profiles to {}: {e}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Someone's rating; new players start at the initial rating.
+    pub fn rating(&self, name: &str) -> f64 {
+        self.get(name).map_or(rating::INITIAL, |p| p.rating)
+    }
+
+    pub fn ai_rating(&self, difficulty: AIDifficulty) -> f64 {
+        self.ai_ratings[slot(difficulty)]
+    }
+
+    /// The AI difficulty rated closest to `rating`, for an even game. Medium wins ties, so newcomers start there.
+    pub fn closest_difficulty(&self, rating: f64) -> AIDifficulty {
+        [AIDifficulty::Medium, AIDifficulty::Easy, AIDifficulty::Hard]
+            .into_iter()
+            .min_by(|a, b| (self.ai_rating(*a) - rating).abs().total_cmp(&(self.ai_rating(*b) - rating).abs()))
+            .unwrap_or(AIDifficulty::Medium)
+    }
+
+    fn get_or_create(&mut self, name: &str) -> &mut Profile {
+        match self.profiles.iter().position(|p| p.name.eq_ignore_ascii_case(name)) {
+            Some(index) => &mut self.profiles[index],
+            None => {
+                self.profiles.push(Profile::new(name));
+                self.profiles.last_mut().unwrap()
+            }
+        }
+    }
+}
+
+/// One person's game so far.
+#[derive(Default)]
+struct GameStats {
+    longest_stack: u32,
+    wild_colors: [u32; 4],
+}
+
+/// How a finished game went, for adding to the profiles of the people in it.
+pub struct Outcome {
+    /// Seat numbers of the people playing, their names, and how their game went.
+    people: Vec<(usize, String, GameStats)>,
+    difficulty: Option<AIDifficulty>,
+    winner: usize,
+    cards_left: Vec<usize>,
+}
+
+impl Outcome {
+    pub fn apply(self, profiles: &mut Profiles) {
+        let places = rating::places(self.winner, &self.cards_left);
+        let name = |seat: usize| self.people.iter().find(|(s, ..)| *s == seat).map(|(_, name, _)| name.as_str());
+        let ai_rating = profiles.ai_rating(self.difficulty.unwrap_or(AIDifficulty::Medium));
+        let ratings = (0..self.cards_left.len()).map(|seat| name(seat).map_or(ai_rating, |name| profiles.rating(name))).collect::<Vec<f64>>();
+        let changes = rating::changes(&ratings, &places);
+
+        // Several AIs at one table are all the same difficulty, so it moves by their average.
+        let ai_changes = (0..changes.len()).filter(|seat| name(*seat).is_none()).map(|seat| changes[seat]).collect::<Vec<f64>>();
+
+        if let (Some(difficulty), false) = (self.difficulty, ai_changes.is_empty()) {
+            profiles.ai_ratings[slot(difficulty)] += ai_changes.iter().sum::<f64>() / ai_changes.len() as f64;
+        }
+
+        for (seat, name, stats) in self.people.iter() {
+            let won = *seat == self.winner;
+            let profile = profiles.get_or_create(name);
+
+            profile.games += 1;
+            profile.wins += won as u32;
+            profile.cards_left += self.cards_left.get(*seat).copied().unwrap_or(0) as u32;
+            profile.longest_stack = profile.longest_stack.max(stats.longest_stack);
+            profile.rating += changes[*seat];
+
+            if let Some(difficulty) = self.difficulty {
+                let tally = &mut profile.by_difficulty[slot(difficulty)];
+                tally.games += 1;
+                tally.wins += won as u32;
+            }
+
+            for (total, count) in profile.wild_colors.iter_mut().zip(stats.wild_colors) {
+                *total += count;
+            }
+        }
+    }
+}
+
+/// Keeps the people at the table's profiles up to date. Only finished games count.
+pub struct ProfileTracker {
+    /// Seat numbers of the people playing, and their names.
+    people: Vec<(usize, String)>,
+    /// The AI difficulty, if there are AIs at the table.
+    difficulty: Option<AIDifficulty>,
+    to_draw: u8,
+    games: HashMap<usize, GameStats>,
+    /// Where the outcome goes instead of straight into the profiles file, if somewhere else keeps the profiles.
+    outcomes: Option<Sender<Outcome>>,
+}
+
+impl ProfileTracker {
+    pub fn new(seats: &[Seat], difficulty: Option<AIDifficulty>) -> ProfileTracker {
+        ProfileTracker {
+            people: seats.iter().enumerate().filter(|(_, s)| s.human).map(|(i, s)| (i, s.name.clone())).collect(),
+            difficulty,
+            to_draw: 0,
+            games: HashMap::new(),
+            outcomes: None,
+        }
+    }
+
+    /// Sends the outcome to `outcomes` at the end of the game, for whoever holds the profiles to add.
+    pub fn send_outcome(&mut self, outcomes: Sender<Outcome>) {
+        self.outcomes = Some(outcomes);
+    }
+
+    fn finish(&mut self, winner: usize, table: &Table) {
+        let outcome = Outcome {
+            people: self.people.iter().map(|(seat, name)| (*seat, name.clone(), self.games.remove(seat).unwrap_or_default())).collect(),
+            difficulty: self.difficulty,
+            winner,
+            cards_left: table.hands.iter().map(|hand| hand.len()).collect(),
+        };
+
+        if let Some(outcomes) = &self.outcomes {
+            let _ = outcomes.send(outcome);
+            return;
+        }
+
+        let mut profiles = match Profiles::load() {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                // Better to lose one game's statistics than to overwrite a file that might be fixable.
+                println!("{e}");
+                return;
+            }
+        };
+
+        outcome.apply(&mut profiles);
+
+        if let Err(e) = profiles.save() {
+            println!("{e}");
+        }
+    }
+}
+
+impl GameObserver for ProfileTracker {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let is_person = |seat: &usize| self.people.iter().any(|(s, _)| s == seat);
+
+        match event {
+            GameEvent::Started { .. } => self.games.clear(),
+            GameEvent::TurnStarted { .. } => self.to_draw = table.to_draw,
+            GameEvent::Drew { player, count } if is_person(player) && self.to_draw > 0 => {
+                let stats = self.games.entry(*player).or_default();
+                stats.longest_stack = stats.longest_stack.max(*count as u32);
+            }
+            GameEvent::Played { player, card: Card::Wild { color: Some(color) } | Card::DrawFour { color: Some(color) } } if is_person(player) => {
+                let index = COLORS.iter().position(|c| c == color).unwrap_or(0);
+                self.games.entry(*player).or_default().wild_colors[index] += 1;
+            }
+            GameEvent::Won { player } => self.finish(*player, table),
+            _ => {}
+        }
+    }
+}
+
+fn percent(wins: u32, games: u32) -> String {
+    if games == 0 { "-".to_string() } else { format!("{:.0}%", wins as f64 / games as f64 * 100.0) }
+}
+
+/// Prints one profile, or everyone's if `name` is `None`.
+pub fn print_stats(name: Option<&str>) {
+    let profiles = match Profiles::load() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    let shown = match name {
+        Some(name) => match profiles.get(name) {
+            Some(profile) => vec![profile],
+            None => {
+                println!("There's no profile called {name} yet. Profiles are made when someone finishes a game.");
+                return;
+            }
+        },
+        None => profiles.profiles.iter().collect(),
+    };
+
+    if shown.is_empty() {
+        println!("Nobody has finished a game yet.");
+    }
+
+    for profile in shown {
+        println!("{}", profile.name);
+        println!("  Rating: {:.0}", profile.rating);
+        println!("  Games played: {} ({} won, {})", profile.games, profile.wins, percent(profile.wins, profile.games));
+
+        for difficulty in DIFFICULTIES {
+            let tally = profile.by_difficulty[slot(difficulty)];
+
+            if tally.games > 0 {
+                println!("  Against {difficulty} AIs: {} won of {} ({})", tally.wins, tally.games, percent(tally.wins, tally.games));
+            }
+        }
+
+        println!("  Average cards left at the end: {:.1}", profile.average_cards_left());
+        println!("  Longest stack absorbed: {} cards", profile.longest_stack);
+
+        match profile.favorite_wild_color() {
+            Some(color) => println!(
+                "  Favorite wild color: {color} ({} of {} wild cards)",
+                profile.wild_colors[COLORS.iter().position(|c| *c == color).unwrap_or(0)],
+                profile.wild_colors.iter().sum::<u32>()
+            ),
+            None => println!("  Favorite wild color: none yet"),
+        }
+    }
+
+    print_ai_ratings(&profiles);
+}
+
+/// Prints how `names`' ratings have moved since `before`.
+pub fn print_rating_changes(before: &Profiles, names: &[String]) {
+    let Ok(after) = Profiles::load() else { return };
+
+    for name in names {
+        let (old, new) = (before.rating(name), after.rating(name));
+        println!("{name}'s rating: {new:.0} ({:+.0})", new - old);
+    }
+}
+
+fn print_ai_ratings(profiles: &Profiles) {
+    let ratings = DIFFICULTIES.map(|d| format!("{d} {:.0}", profiles.ai_rating(d)));
+
+    println!("AI ratings: {}", ratings.join(", "));
+}
+
+/// Ranks everyone by rating, then by win rate.
+pub fn print_leaderboard() {
+    let all = match Profiles::load() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    let mut profiles = all.profiles.clone();
+
+    if profiles.is_empty() {
+        println!("Nobody has finished a game yet.");
+        return;
+    }
+
+    profiles.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(b.win_rate().total_cmp(&a.win_rate())).then(a.name.cmp(&b.name)));
+
+    let width = profiles.iter().map(|p| p.name.chars().count()).max().unwrap_or(0).max(4);
+
+    println!("  # {:width$}  Rating  Games  Wins  Win rate  Cards left", "Name");
+
+    for (rank, profile) in profiles.iter().enumerate() {
+        println!(
+            "{:>3} {:width$}  {:>6.0}  {:>5}  {:>4}  {:>8}  {:>10.1}",
+            rank + 1,
+            profile.name,
+            profile.rating,
+            profile.games,
+            profile.wins,
+            percent(profile.wins, profile.games),
+            profile.average_cards_left()
+        );
+    }
+
+    println!();
+    print_ai_ratings(&all);
+}
@@ -0,0 +1,103 @@
+//! Multiplayer Elo ratings. A game is scored as if everyone had played everyone else: each pair counts as one game
+//! won by whoever finished higher, with the usual Elo update shared out over the other players so a game is worth
+//! the same however many sit at the table.
+
+/// Where every profile and AI difficulty starts.
+pub const INITIAL: f64 = 1500.0;
+/// Most a rating can move in one game.
+const K: f64 = 32.0;
+
+/// Finishing places, 0 for the winner. Only the winner goes out, so everyone else is placed by how many cards they
+/// were left holding; equal hands share a place.
+pub fn places(winner: usize, cards_left: &[usize]) -> Vec<usize> {
+    cards_left
+        .iter()
+        .enumerate()
+        .map(|(seat, cards)| {
+            if seat == winner {
+                0
+            } else {
+                1 + cards_left.iter().enumerate().filter(|(other, c)| *other != winner && *c < cards).count()
+            }
+        })
+        .collect()
+}
+
+fn expected(rating: f64, other: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((other - rating) / 400.0))
+}
+
+/// How much each rating moves after a game with the given finishing `places`.
+pub fn changes(ratings: &[f64], places: &[usize]) -> Vec<f64> {
+    let opponents = ratings.len().saturating_sub(1).max(1) as f64;
+
+    ratings
+        .iter()
+        .enumerate()
+        .map(|(i, rating)| {
+            let (score, expected) = ratings
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(j, other)| {
+                    let score = match places[i].cmp(&places[j]) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+
+                    (score, expected(*rating, *other))
+                })
+                .fold((0.0, 0.0), |(s, e), (score, expected)| (s + score, e + expected));
+
+            K * (score - expected) / opponents
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} is not {b}");
+    }
+
+    #[test]
+    fn places_by_cards_left() {
+        assert_eq!(places(2, &[5, 1, 0, 5]), [2, 1, 0, 2]);
+        assert_eq!(places(0, &[0, 3]), [0, 1]);
+    }
+
+    #[test]
+    fn changes_add_up_to_nothing() {
+        let ratings = [1500.0, 1720.0, 1310.0, 1605.0];
+
+        for places in [[0, 1, 2, 3], [3, 0, 1, 1], [1, 2, 2, 0]] {
+            assert_close(changes(&ratings, &places).iter().sum(), 0.0);
+        }
+    }
+
+    #[test]
+    fn the_winner_gains_and_last_place_loses() {
+        // Even the best-rated player loses points for coming last to weaker ones.
+        let changes = changes(&[1200.0, 1400.0, 1800.0], &[0, 1, 2]);
+
+        assert!(changes[0] > 0.0, "{changes:?}");
+        assert!(changes[2] < 0.0, "{changes:?}");
+    }
+
+    #[test]
+    fn equal_ratings_move_in_steps_of_k_over_the_opponents() {
+        for players in 2..=6 {
+            let changes = changes(&vec![INITIAL; players], &(0..players).collect::<Vec<usize>>());
+            let step = K / (players - 1) as f64;
+
+            assert_close(changes[0], K / 2.0);
+
+            for pair in changes.windows(2) {
+                assert_close(pair[0] - pair[1], step);
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! Game records. A record is a text file with one JSON object per line: a header with the seed, the rules and the
+//! seats, then the initial deal, then every action in the order it happened. Actions are game events as `serialization`
+//! writes them, and cards are in card notation (see `Card::from_str`), so a wild card keeps the color that was chosen
+//! for it.
+//!
+//! ```text
+//! {"type":"header","format":"uno-record","version":1,"seed":42,"rules":{"hand_size":7,"stacking":true},"seats":["Rin","Sam"]}
+//! {"type":"deal","hands":[["R5","GS",...],["W","B+2",...]],"top_card":"Y3"}
+//! {"type":"played","player":1,"card":"W:R"}
+//! {"type":"drew","player":0,"count":1}
+//! ```
+//!
+//! Since every shuffle follows from the seed, a record can be checked by playing it again: see [`simulate`].
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::card::Card;
+use crate::game::{GameEvent, GameObserver, GameState, Stop, Table, Turn, TurnResult};
+use crate::player::Player;
+use crate::rules::RuleSet;
+
+const FORMAT: &str = "uno-record";
+const VERSION: u64 = 1;
+
+/// The lines of a record that aren't actions.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Line {
+    Header { format: String, version: u64, seed: u64, rules: RuleSet, seats: Vec<String> },
+    Deal { hands: Vec<Vec<Card>>, top_card: Card },
+}
+
+/// A game as read back from a record file.
+pub struct Record {
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub seats: Vec<String>,
+    /// Each seat's hand as dealt.
+    pub deal: Vec<Vec<Card>>,
+    pub top_card: Card,
+    /// Everything that happened after the deal. Turn starts aren't recorded.
+    pub events: Vec<GameEvent>,
+}
+
+impl Record {
+    pub fn load(path: &Path) -> Result<Record, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        let line_error = |number: usize, e: String| format!("Line {}: {e}", number + 1);
+
+        let (number, header) = lines.next().ok_or("The record is empty.")?;
+        let header = parse_line(header).map_err(|e| line_error(number, e))?;
+
+        if header["type"] != "header" || header["format"] != FORMAT {
+            return Err("This is not a game record.".to_string());
+        }
+
+        if header["version"].as_u64() != Some(VERSION) {
+            return Err(format!("This record is version {}; only version {VERSION} can be read.", header["version"]));
+        }
+
+        let Line::Header { seed, rules, seats, .. } = read(&header).map_err(|e| line_error(number, e))? else {
+            return Err("This is not a game record.".to_string());
+        };
+
+        let (number, deal) = lines.next().ok_or("The record stops before the deal.")?;
+        let Line::Deal { hands, top_card } = parse_line(deal).and_then(|deal| read(&deal)).map_err(|e| line_error(number, e))? else {
+            return Err(line_error(number, "expected the deal".to_string()));
+        };
+
+        if hands.len() != seats.len() {
+            return Err(line_error(number, format!("there are {} hands for {} seats", hands.len(), seats.len())));
+        }
+
+        let events = lines
+            .map(|(number, line)| parse_line(line).and_then(|value| event(&value, seats.len())).map_err(|e| line_error(number, e)))
+            .collect::<Result<Vec<GameEvent>, String>>()?;
+
+        Ok(Record { seed, rules, seats, deal: hands, top_card, events })
+    }
+}
+
+fn parse_line(line: &str) -> Result<Value, String> {
+    serde_json::from_str(line).map_err(|e| format!("not valid JSON ({e})"))
+}
+
+fn read<'de, T: Deserialize<'de>>(value: &'de Value) -> Result<T, String> {
+    T::deserialize(value).map_err(|e| e.to_string())
+}
+
+fn event(value: &Value, seats: usize) -> Result<GameEvent, String> {
+    match read(value)? {
+        GameEvent::Started { .. } | GameEvent::TurnStarted { .. } => Err(format!("{} is not an action", value["type"])),
+        GameEvent::Played { player, .. } | GameEvent::Drew { player, .. } | GameEvent::Skipped { player } | GameEvent::Won { player }
+            if player >= seats => Err(format!("{player} is not a seat")),
+        event => Ok(event),
+    }
+}
+
+/// The event as it's written to a record, or `None` for the ones that aren't recorded.
+fn event_json(event: &GameEvent) -> Option<Value> {
+    match event {
+        GameEvent::Started { .. } | GameEvent::TurnStarted { .. } => None,
+        event => serde_json::to_value(event).ok(),
+    }
+}
+
+/// Writes the game to a record as it's played. Every line is written straight away, so a game that's quit halfway
+/// still leaves a readable record of what happened up to then.
+pub struct RecordWriter<W: Write = File> {
+    out: W,
+    seed: u64,
+    rules: RuleSet,
+}
+
+impl RecordWriter {
+    pub fn create(path: &Path, seed: u64, rules: RuleSet) -> std::io::Result<RecordWriter> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        Ok(RecordWriter::new(File::create(path)?, seed, rules))
+    }
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(out: W, seed: u64, rules: RuleSet) -> RecordWriter<W> {
+        RecordWriter { out, seed, rules }
+    }
+
+    /// What's been written so far.
+    #[cfg_attr(not(feature = "history"), allow(dead_code))]
+    pub fn output(&self) -> &W {
+        &self.out
+    }
+
+    fn write(&mut self, line: &impl Serialize) {
+        // A full disk shouldn't end the game; the record is just cut short.
+        if let Ok(line) = serde_json::to_string(line) {
+            let _ = writeln!(self.out, "{line}");
+        }
+    }
+}
+
+impl<W: Write + Send> GameObserver for RecordWriter<W> {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        match event {
+            GameEvent::Started { players, top_card } => {
+                self.write(&Line::Header {
+                    format: FORMAT.to_string(),
+                    version: VERSION,
+                    seed: self.seed,
+                    rules: self.rules,
+                    seats: players.clone(),
+                });
+                self.write(&Line::Deal { hands: table.hands.iter().map(|hand| hand.to_vec()).collect(), top_card: *top_card });
+            }
+            GameEvent::TurnStarted { .. } => {}
+            event => self.write(event),
+        }
+    }
+}
+
+/// What's left of the record while it's being played again, and the first thing found wrong with it.
+struct Script {
+    seats: Vec<String>,
+    deal: Vec<Vec<Card>>,
+    top_card: Card,
+    events: VecDeque<GameEvent>,
+    step: usize,
+    error: Option<String>,
+    stop: Stop,
+}
+
+impl Script {
+    fn describe(&self, event: &GameEvent) -> String {
+        let name = |seat: &usize| self.seats.get(*seat).map_or("Someone", |n| n.as_str());
+
+        match event {
+            GameEvent::Played { player, card } => format!("{} playing {}", name(player), card.notation()),
+            GameEvent::Drew { player, count } => format!("{} drawing {count}", name(player)),
+            GameEvent::Skipped { player } => format!("{} being skipped", name(player)),
+            GameEvent::Won { player } => format!("{} winning", name(player)),
+            GameEvent::Started { .. } | GameEvent::TurnStarted { .. } => "the game starting".to_string(),
+        }
+    }
+
+    /// Stops the game being played again, keeping the first thing found wrong as the reason.
+    fn abort(&mut self, error: String) {
+        self.error.get_or_insert(error);
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Makes a seat's moves from the record.
+struct ReplaySeat {
+    seat: usize,
+    name: String,
+    script: Arc<Mutex<Script>>,
+}
+
+impl Player for ReplaySeat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
+        let mut script = self.script.lock().unwrap();
+        let step = script.step + 1;
+
+        let error = match script.events.front() {
+            Some(GameEvent::Played { player, card }) if *player == self.seat => {
+                let card = *card;
+
+                if !turn.playable_hand.contains(&card) {
+                    format!("Step {step}: {} plays {}, which they can't play on {}.", self.name, card.notation(), turn.last_card.notation())
+                } else if matches!(card, Card::Wild { color: None } | Card::DrawFour { color: None }) {
+                    format!("Step {step}: {} plays a wild card without choosing a color.", self.name)
+                } else {
+                    return TurnResult::Played(card);
+                }
+            }
+            Some(GameEvent::Drew { player, .. }) if *player == self.seat => return TurnResult::Drew,
+            Some(event) => format!("Step {step}: it's {}'s turn, but the record has {}.", self.name, script.describe(event)),
+            None => format!("The record stops after step {}, before anyone won.", step - 1),
+        };
+
+        // The game stops before making whatever move this is.
+        script.abort(error);
+        TurnResult::Drew
+    }
+
+    fn observe_turn(&self, _other: &dyn Player, _card: &Card) {}
+
+    fn observe_turn_skip(&mut self, _observed_cards: Option<Vec<&Card>>) {}
+}
+
+/// Compares what the game does with what the record says it did.
+struct Checker(Arc<Mutex<Script>>);
+
+impl GameObserver for Checker {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let mut script = self.0.lock().unwrap();
+
+        if script.error.is_some() {
+            return;
+        }
+
+        match event {
+            GameEvent::TurnStarted { .. } => {}
+            GameEvent::Started { top_card, .. } => {
+                for (seat, hand) in table.hands.iter().enumerate() {
+                    if *hand != script.deal[seat].as_slice() {
+                        let error = format!("The seed deals {} a different hand from the one recorded.", script.seats[seat]);
+                        return script.abort(error);
+                    }
+                }
+
+                if top_card.notation() != script.top_card.notation() {
+                    let error = format!("The seed turns up {} as the top card, not {}.", top_card.notation(), script.top_card.notation());
+                    script.abort(error);
+                }
+            }
+            event => {
+                script.step += 1;
+                let step = script.step;
+
+                let Some(expected) = script.events.pop_front() else {
+                    let error = format!("Step {step}: the game went on with {}, but the record stops there.", script.describe(event));
+                    return script.abort(error);
+                };
+
+                if event_json(&expected) != event_json(event) {
+                    let error = format!("Step {step}: the record has {}, but the game went on with {}.", script.describe(&expected), script.describe(event));
+                    script.abort(error);
+                }
+            }
+        }
+    }
+}
+
+/// Plays the recorded game again from its seed, checking every action against the rules and the record, and returns
+/// the winner. `observer` watches the game as it's played again.
+pub fn simulate(record: &Record, observer: Option<Box<dyn GameObserver>>) -> Result<String, String> {
+    let stop = Stop::new(AtomicBool::new(false));
+    let script = Arc::new(Mutex::new(Script {
+        seats: record.seats.clone(),
+        deal: record.deal.clone(),
+        top_card: record.top_card,
+        events: record.events.iter().cloned().collect(),
+        step: 0,
+        error: None,
+        stop: stop.clone(),
+    }));
+
+    let players = record
+        .seats
+        .iter()
+        .enumerate()
+        .map(|(seat, name)| Box::new(ReplaySeat { seat, name: name.clone(), script: script.clone() }) as Box<dyn Player>)
+        .collect();
+
+    let mut game = GameState::new(players, record.rules);
+    game.set_seed(record.seed);
+    game.set_turn_delay(Duration::ZERO);
+    game.set_stop(stop);
+    game.add_observer(Box::new(Checker(script.clone())));
+
+    if let Some(observer) = observer {
+        game.add_observer(observer);
+    }
+
+    let winner = game.start();
+    let mut script = script.lock().unwrap();
+
+    match winner {
+        Some(_) if !script.events.is_empty() => Err(format!("Step {}: the record goes on after the game was won.", script.step + 1)),
+        Some(winner) => Ok(winner),
+        None => Err(script.error.take().unwrap_or_else(|| "The game could not be played again.".to_string())),
+    }
+}
+
+/// Checks a record file and prints the verdict. Returns whether it checked out.
+pub fn verify(path: &Path) -> bool {
+    let record = match Record::load(path) {
+        Ok(record) => record,
+        Err(e) => {
+            println!("{e}");
+            return false;
+        }
+    };
+
+    match simulate(&record, None) {
+        Ok(winner) => {
+            println!("The record is consistent: {} actions from seed {}, and {winner} won.", record.events.len(), record.seed);
+            true
+        }
+        Err(e) => {
+            println!("The record is inconsistent. {e}");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::player::{AIDifficulty, Ai};
+
+    /// Plays a seeded game between AIs, recording it to `path`, and returns the winner.
+    fn record_game(path: &Path, seed: u64) -> String {
+        let players = (0..3)
+            .map(|seat| {
+                let mut ai = Ai::new(StdRng::seed_from_u64(seat), AIDifficulty::Hard);
+                ai.set_name(&format!("seat {seat}"));
+                Box::new(ai) as Box<dyn Player>
+            })
+            .collect();
+
+        let mut game = GameState::new(players, RuleSet::default());
+        game.set_seed(seed);
+        game.set_turn_delay(Duration::ZERO);
+        game.add_observer(Box::new(RecordWriter::create(path, seed, RuleSet::default()).unwrap()));
+
+        game.start().unwrap()
+    }
+
+    #[test]
+    fn verifies_a_record_and_notices_tampering() {
+        let path = std::env::temp_dir().join(format!("uno_cli-test-record-{}.jsonl", std::process::id()));
+        let winner = record_game(&path, 7);
+
+        let record = Record::load(&path).unwrap();
+        assert_eq!(record.seats, ["seat 0", "seat 1", "seat 2"]);
+        assert_eq!(simulate(&record, None), Ok(winner));
+
+        // Turn the first card played into a draw instead.
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines().map(str::to_string).collect::<Vec<String>>();
+        let played = lines.iter().position(|line| line.contains(r#""type":"played""#)).unwrap();
+        let player = parse_line(&lines[played]).unwrap()["player"].clone();
+        lines[played] = json!({ "type": "drew", "player": player, "count": 1 }).to_string();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let tampered = Record::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // The draw itself is allowed, so the record only goes wrong once the game stops following it.
+        let error = simulate(&tampered, None).unwrap_err();
+        assert!(error.starts_with("Step "), "{error}");
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::card::Card;
+use crate::console::describe;
+use crate::game::{Direction, GameEvent, GameObserver, GameState, Table};
+use crate::record::{self, Record};
+
+/// The table right after one step of a recorded game.
+struct Frame {
+    text: String,
+    names: Vec<String>,
+    hands: Vec<Vec<Card>>,
+    top_card: Option<Card>,
+    current_player: usize,
+    direction: Direction,
+}
+
+/// Keeps a frame for every step as the record is played again.
+struct Frames(Arc<Mutex<Vec<Frame>>>);
+
+impl GameObserver for Frames {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        if matches!(event, GameEvent::TurnStarted { .. }) {
+            return;
+        }
+
+        self.0.lock().unwrap().push(Frame {
+            text: describe(event, table, GameState::get_colorized_card_name),
+            names: table.names.iter().map(|n| n.to_string()).collect(),
+            hands: table.hands.iter().map(|hand| hand.to_vec()).collect(),
+            top_card: table.top_card,
+            current_player: table.current_player,
+            direction: table.direction,
+        });
+    }
+}
+
+fn print_frame(frame: &Frame, step: usize, steps: usize) {
+    println!();
+    println!("Step {step} of {steps}: {}", frame.text);
+
+    let arrow = match frame.direction {
+        Direction::Clockwise => "↓",
+        Direction::CounterClockwise => "↑",
+    };
+
+    for (seat, (name, hand)) in frame.names.iter().zip(frame.hands.iter()).enumerate() {
+        let marker = if seat == frame.current_player { arrow } else { " " };
+        let cards = hand.iter().map(|c| GameState::get_colorized_card_name(*c)).collect::<Vec<String>>();
+
+        println!(" {marker} {name} ({}): {}", hand.len(), cards.join(", "));
+    }
+
+    if let Some(top_card) = frame.top_card {
+        println!("Top card: {}", GameState::get_colorized_card_name(top_card));
+    }
+}
+
+/// Steps through a recorded game from the keyboard, showing every hand.
+pub fn run(path: &Path) {
+    let record = match Record::load(path) {
+        Ok(record) => record,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    let frames = Arc::new(Mutex::new(vec![]));
+
+    if let Err(e) = record::simulate(&record, Some(Box::new(Frames(frames.clone())))) {
+        println!("This record doesn't check out, so only the part before the problem can be shown. {e}");
+    }
+
+    let frames = frames.lock().unwrap();
+
+    if frames.is_empty() {
+        return;
+    }
+
+    println!("Replaying {} (seed {}).", record.seats.join(" vs "), record.seed);
+    println!("Press Enter or [N] for the next step, [B] to go back, type a step number to jump to it, or [Q] to quit.");
+
+    let last = frames.len() - 1;
+    let mut step = 0;
+
+    loop {
+        print_frame(&frames[step], step, last);
+
+        let mut input = String::new();
+
+        if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            return;
+        }
+
+        let input = input.trim().to_lowercase();
+
+        match input.as_str() {
+            "" | "n" | "next" if step == last => println!("That was the last step."),
+            "" | "n" | "next" => step += 1,
+            "b" | "back" if step == 0 => println!("This is the deal; there's nothing before it."),
+            "b" | "back" => step -= 1,
+            "q" | "quit" => return,
+            _ => match input.parse::<usize>() {
+                Ok(number) if number <= last => step = number,
+                _ => println!("Type n, b, q or a step number from 0 to {last}."),
+            },
+        }
+    }
+}
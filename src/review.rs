@@ -0,0 +1,309 @@
+//! Post-game review. A [`Recorder`] writes down every turn where the human had a real choice; afterwards each choice
+//! is scored against the alternatives by playing the rest of the game out many times from that position.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::card::{Card, CardColor, Deck};
+use crate::game::{Direction, GameEvent, GameObserver, GameState, Position, Table, Turn, TurnResult};
+use crate::player::{AIDifficulty, Ai, Player};
+use crate::rules::RuleSet;
+
+/// Games played out per option when scoring a turn.
+const ROLLOUTS: usize = 40;
+/// Win chance given up, compared with the best option, before a choice is called a mistake or a blunder.
+const MISTAKE: f64 = 0.1;
+const BLUNDER: f64 = 0.2;
+
+const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+
+/// A turn where the human could have played something, and what they did.
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub turn: Turn,
+    pub choice: TurnResult,
+}
+
+/// Writes down one player's decisions as the game goes.
+pub struct Recorder {
+    seat: usize,
+    rules: RuleSet,
+    log: Arc<Mutex<Vec<Decision>>>,
+    pending: Option<Turn>,
+}
+
+impl Recorder {
+    pub fn new(seat: usize, rules: RuleSet, log: Arc<Mutex<Vec<Decision>>>) -> Recorder {
+        Recorder {
+            seat,
+            rules,
+            log,
+            pending: None,
+        }
+    }
+
+    /// The turn as the player saw it, rebuilt from the table.
+    fn turn(&self, seat: usize, table: &Table) -> Option<Turn> {
+        let last_card = table.top_card?;
+        let full_hand = table.hands[seat].to_vec();
+        let count = table.hands.len();
+
+        let playable_hand = full_hand
+            .iter()
+            .filter(|c| self.rules.check_play(c, &last_card, table.to_draw).is_ok())
+            .copied()
+            .collect::<Vec<Card>>();
+
+        let opponents = (1..count)
+            .map(|offset| match table.direction {
+                Direction::Clockwise => (seat + offset) % count,
+                Direction::CounterClockwise => (seat + count - offset) % count,
+            })
+            .map(|other| table.hands[other].len())
+            .collect();
+
+        Some(Turn {
+            to_draw: table.to_draw,
+            full_hand,
+            playable_hand,
+            last_card,
+            rules: self.rules,
+            opponents,
+        })
+    }
+}
+
+impl GameObserver for Recorder {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let is_me = |player: &usize| *player == self.seat;
+
+        let choice = match event {
+            GameEvent::Started { .. } => {
+                self.log.lock().unwrap().clear();
+                return;
+            }
+            GameEvent::TurnStarted { player } if is_me(player) => {
+                // Forced draws aren't decisions.
+                self.pending = self.turn(*player, table).filter(|turn| !turn.playable_hand.is_empty());
+                return;
+            }
+            GameEvent::Played { player, card } if is_me(player) => TurnResult::Played(*card),
+            GameEvent::Drew { player, .. } if is_me(player) => TurnResult::Drew,
+            _ => return,
+        };
+
+        if let Some(turn) = self.pending.take() {
+            self.log.lock().unwrap().push(Decision { turn, choice });
+        }
+    }
+}
+
+/// Makes one scripted move, then plays like an AI.
+struct Scripted {
+    first: Option<TurnResult>,
+    ai: Ai<StdRng>,
+}
+
+impl Player for Scripted {
+    fn name(&self) -> &str {
+        "reviewed seat"
+    }
+
+    fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
+        self.first.take().unwrap_or_else(|| self.ai.execute_turn(turn))
+    }
+
+    fn observe_turn(&self, _other: &dyn Player, _card: &Card) {}
+
+    fn observe_turn_skip(&mut self, _observed_cards: Option<Vec<&Card>>) {}
+}
+
+/// Everything that could be done with `turn`, wild cards once per color.
+fn options(turn: &Turn) -> Vec<TurnResult> {
+    let mut options = vec![TurnResult::Drew];
+
+    for card in turn.playable_hand.iter() {
+        let plays = if matches!(card, Card::Wild { .. } | Card::DrawFour { .. }) {
+            COLORS
+                .iter()
+                .map(|color| {
+                    let mut card = *card;
+                    TurnResult::Played(*card.with_color(*color).unwrap())
+                })
+                .collect()
+        } else {
+            vec![TurnResult::Played(*card)]
+        };
+
+        for play in plays {
+            if !options.iter().any(|option| same(option, &play)) {
+                options.push(play);
+            }
+        }
+    }
+
+    options
+}
+
+/// Equality that, unlike `Card`'s, tells wild colors apart.
+fn same(a: &TurnResult, b: &TurnResult) -> bool {
+    match (a, b) {
+        (TurnResult::Played(a), TurnResult::Played(b)) => a.notation() == b.notation(),
+        (TurnResult::Drew, TurnResult::Drew) => true,
+        _ => false,
+    }
+}
+
+/// How often `choice` goes on to win, over games played out from `turn` with the hidden hands dealt at random.
+fn win_rate(turn: &Turn, choice: TurnResult, rng: &mut StdRng) -> f64 {
+    let mut wins = 0;
+
+    for _ in 0..ROLLOUTS {
+        let mut unseen = Deck::new_deck();
+
+        for card in turn.full_hand.iter().chain([turn.last_card].iter()) {
+            if let Some(index) = unseen.iter().position(|c| c == card) {
+                unseen.remove(index);
+            }
+        }
+
+        unseen.shuffle(rng);
+
+        let mut hands = vec![turn.full_hand.clone()];
+
+        for size in turn.opponents.iter() {
+            hands.push(unseen.split_off(unseen.len().saturating_sub(*size)));
+        }
+
+        let mut players: Vec<Box<dyn Player>> = vec![Box::new(Scripted {
+            first: Some(choice),
+            ai: Ai::new(StdRng::seed_from_u64(rng.gen()), AIDifficulty::Hard),
+        })];
+
+        for _ in turn.opponents.iter() {
+            players.push(Box::new(Ai::new(StdRng::seed_from_u64(rng.gen()), AIDifficulty::Hard)));
+        }
+
+        let position = Position {
+            deck: unseen,
+            discard: vec![turn.last_card],
+            hands,
+            to_move: 0,
+            direction: Direction::Clockwise,
+            to_draw: turn.to_draw,
+        };
+
+        let mut game = GameState::from_position(players, turn.rules, position);
+        game.set_turn_delay(Duration::ZERO);
+
+        if game.play().as_deref() == Some("reviewed seat") {
+            wins += 1;
+        }
+    }
+
+    wins as f64 / ROLLOUTS as f64
+}
+
+fn describe(choice: &TurnResult) -> String {
+    match choice {
+        TurnResult::Played(card) => format!("play {card}"),
+        TurnResult::Drew => "draw".to_string(),
+    }
+}
+
+/// Rules of thumb worth pointing out whatever the numbers say.
+fn notes(decision: &Decision) -> Vec<String> {
+    let turn = &decision.turn;
+    let mut notes = vec![];
+
+    let attacks = |c: &Card| matches!(c, Card::Skip { .. } | Card::Reverse { .. } | Card::DrawTwo { .. } | Card::DrawFour { .. });
+
+    if turn.opponents.first() == Some(&1) && !matches!(decision.choice, TurnResult::Played(card) if attacks(&card)) {
+        if let Some(card) = turn.playable_hand.iter().find(|c| attacks(c)) {
+            notes.push(format!("The next player had one card left and you held {card}."));
+        }
+    }
+
+    if let TurnResult::Played(card @ (Card::Wild { color: Some(color) } | Card::DrawFour { color: Some(color) })) = decision.choice {
+        let mut rest = turn.full_hand.clone();
+        rest.remove(rest.iter().position(|c| *c == card).unwrap_or(0));
+
+        let held = rest.iter().any(|c| c.color() == Some(color));
+        let other = rest.iter().find_map(|c| c.color());
+
+        if let (false, Some(other)) = (held, other) {
+            notes.push(format!("You chose {color} but held no {} cards; {other} was an option.", color.to_string().to_lowercase()));
+        }
+    }
+
+    notes
+}
+
+/// Scores every decision and prints the review, ending with a summary.
+pub fn print(decisions: &[Decision]) {
+    if decisions.is_empty() {
+        println!("There's nothing to review: you never had a choice to make.");
+        return;
+    }
+
+    println!("Reviewing {} turns...", decisions.len());
+
+    let mut rng = StdRng::from_entropy();
+    let mut best_moves = 0;
+    let mut mistakes = 0;
+    let mut blunders = 0;
+    let mut lost = 0.0;
+
+    for (number, decision) in decisions.iter().enumerate() {
+        let turn = &decision.turn;
+
+        let scored = options(turn)
+            .into_iter()
+            .map(|option| (option, win_rate(turn, option, &mut rng)))
+            .collect::<Vec<(TurnResult, f64)>>();
+
+        let chosen = scored.iter().find(|(option, _)| same(option, &decision.choice)).map_or(0.0, |(_, rate)| *rate);
+        let (best, best_rate) = scored.iter().copied().fold((decision.choice, chosen), |best, next| if next.1 > best.1 { next } else { best });
+        let loss = best_rate - chosen;
+
+        let verdict = if same(&best, &decision.choice) || loss < MISTAKE {
+            best_moves += same(&best, &decision.choice) as usize;
+            "fine"
+        } else if loss < BLUNDER {
+            mistakes += 1;
+            "mistake"
+        } else {
+            blunders += 1;
+            "blunder"
+        };
+
+        lost += loss;
+
+        println!(
+            "Turn {}: on {}, you chose to {} ({:.0}% to win) - {verdict}.",
+            number + 1,
+            turn.last_card,
+            describe(&decision.choice),
+            chosen * 100.0
+        );
+
+        if verdict != "fine" {
+            println!("  Better: {} ({:.0}% to win).", describe(&best), best_rate * 100.0);
+        }
+
+        for note in notes(decision) {
+            println!("  {note}");
+        }
+    }
+
+    println!();
+    println!("Turns reviewed: {}", decisions.len());
+    println!("Best moves: {best_moves}");
+    println!("Mistakes: {mistakes}");
+    println!("Blunders: {blunders}");
+    println!("Average win chance given up: {:.1}%", lost / decisions.len() as f64 * 100.0);
+}
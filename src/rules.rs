@@ -0,0 +1,166 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::card::{Card, CardColor};
+
+/// Table rules a game is played under. `RuleSet::default()` matches the classic game.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RuleSet {
+    /// How many cards each player is dealt.
+    pub hand_size: u8,
+    /// Whether a Draw Two or Draw Four can be answered with another one to pass the penalty on.
+    pub stacking: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            hand_size: 7,
+            stacking: true,
+        }
+    }
+}
+
+/// Why a card may be played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Allowed {
+    SameColor(CardColor),
+    /// Same number, or the same action (Skip on Skip, and so on).
+    SameSymbol,
+    /// Wild cards go on anything.
+    Wild,
+    /// Passes a pending draw penalty on to the next player.
+    Stack,
+}
+
+/// Why a card may not be played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Blocked {
+    /// The card shares neither color nor symbol with the top card.
+    NoMatch { card: Card, top: Card },
+    /// A draw penalty is pending. It can only be answered with `stack_with`, or accepted by drawing; `stack_with` is
+    /// `None` when stacking is off.
+    PendingDraw { to_draw: u8, stack_with: Option<Card> },
+}
+
+impl RuleSet {
+    /// Whether `card` can go on `top` while `to_draw` cards are owed, and why.
+    pub fn check_play(&self, card: &Card, top: &Card, to_draw: u8) -> Result<Allowed, Blocked> {
+        if to_draw > 0 && !self.stacking {
+            return Err(Blocked::PendingDraw { to_draw, stack_with: None });
+        }
+
+        if to_draw > 0 && matches!(top, Card::DrawTwo { .. } | Card::DrawFour { .. }) {
+            return if card == top {
+                Ok(Allowed::Stack)
+            } else {
+                Err(Blocked::PendingDraw { to_draw, stack_with: Some(*top) })
+            };
+        }
+
+        if !card.can_play_on(top) {
+            return Err(Blocked::NoMatch { card: *card, top: *top });
+        }
+
+        match card {
+            Card::Wild { .. } | Card::DrawFour { .. } => Ok(Allowed::Wild),
+            _ if card.color() == top.color() => Ok(Allowed::SameColor(card.color().unwrap())),
+            _ => Ok(Allowed::SameSymbol),
+        }
+    }
+}
+
+/// What's printed in the middle of the card: its number or action.
+fn symbol(card: &Card) -> String {
+    match card {
+        Card::Numeric { value, .. } => value.to_string(),
+        Card::Skip { .. } => "Skip".to_string(),
+        Card::Reverse { .. } => "Reverse".to_string(),
+        Card::DrawTwo { .. } => "Draw Two".to_string(),
+        Card::Wild { .. } => "Wild".to_string(),
+        Card::DrawFour { .. } => "Draw Four".to_string(),
+    }
+}
+
+impl Display for Allowed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Allowed::SameColor(color) => write!(f, "it's {color}, the same color as the top card"),
+            Allowed::SameSymbol => write!(f, "it has the same symbol as the top card"),
+            Allowed::Wild => write!(f, "wild cards can be played on anything"),
+            Allowed::Stack => write!(f, "it stacks on the pending draw and passes it on"),
+        }
+    }
+}
+
+impl Display for Blocked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Blocked::NoMatch { card, top } => {
+                let top_color = top.color().map_or("the top card's color".to_string(), |c| c.to_string());
+                let card_color = card.color().map_or("no color".to_string(), |c| c.to_string());
+
+                write!(f, "it's {card_color}, not {top_color}, and {} isn't {}", symbol(card), symbol(top))
+            }
+            Blocked::PendingDraw { to_draw, stack_with: None } => {
+                write!(f, "{to_draw} cards are waiting for you and stacking is off, so you have to draw them")
+            }
+            Blocked::PendingDraw { to_draw, stack_with: Some(stack_with) } => {
+                let stack_with = match stack_with {
+                    Card::DrawFour { .. } => "another Draw Four".to_string(),
+                    card => format!("another {card}"),
+                };
+
+                write!(f, "{to_draw} cards are waiting for you; answer with {stack_with} to pass them on, or draw them")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardValue;
+
+    const RED_TWO: Card = Card::DrawTwo { color: CardColor::Red };
+
+    #[test]
+    fn a_card_of_another_color_and_symbol_does_not_match() {
+        let (card, top) = (Card::Skip { color: CardColor::Blue }, Card::Reverse { color: CardColor::Red });
+
+        assert_eq!(RuleSet::default().check_play(&card, &top, 0), Err(Blocked::NoMatch { card, top }));
+        assert_eq!(Blocked::NoMatch { card, top }.to_string(), "it's Blue, not Red, and Skip isn't Reverse");
+    }
+
+    #[test]
+    fn a_pending_draw_can_only_be_stacked() {
+        let rules = RuleSet::default();
+        let blocked = Err(Blocked::PendingDraw { to_draw: 2, stack_with: Some(RED_TWO) });
+
+        // Even a card that would match the top card otherwise has to wait.
+        assert_eq!(rules.check_play(&Card::Skip { color: CardColor::Red }, &RED_TWO, 2), blocked);
+        assert_eq!(rules.check_play(&Card::Wild { color: None }, &RED_TWO, 2), blocked);
+        assert_eq!(rules.check_play(&RED_TWO, &RED_TWO, 2), Ok(Allowed::Stack));
+    }
+
+    #[test]
+    fn without_stacking_a_pending_draw_has_to_be_drawn() {
+        let rules = RuleSet { stacking: false, ..RuleSet::default() };
+
+        assert_eq!(
+            rules.check_play(&RED_TWO, &RED_TWO, 2),
+            Err(Blocked::PendingDraw { to_draw: 2, stack_with: None })
+        );
+    }
+
+    #[test]
+    fn explains_why_a_card_is_allowed() {
+        let rules = RuleSet::default();
+        let red_five = Card::Numeric { color: CardColor::Red, value: CardValue::Five };
+        let blue_five = Card::Numeric { color: CardColor::Blue, value: CardValue::Five };
+
+        assert_eq!(rules.check_play(&red_five, &RED_TWO, 0), Ok(Allowed::SameColor(CardColor::Red)));
+        assert_eq!(rules.check_play(&red_five, &blue_five, 0), Ok(Allowed::SameSymbol));
+        assert_eq!(rules.check_play(&Card::DrawFour { color: None }, &red_five, 0), Ok(Allowed::Wild));
+    }
+}
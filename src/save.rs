@@ -0,0 +1,252 @@
+//! Saving a game in progress and picking it up again later. A save is a single JSON object holding the seats and the
+//! position at the start of the turn that was being played, so loading it starts that turn over. The position's
+//! fields sit next to the seats rather than in an object of their own; see `serialization` for how each is written.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::game::{Checkpoint, Position};
+use crate::player::AIDifficulty;
+use crate::rules::RuleSet;
+
+const FORMAT: &str = "uno-save";
+const VERSION: u64 = 1;
+
+static CURRENT: OnceLock<CurrentGame> = OnceLock::new();
+
+/// Who sits where. AIs without a personality get their difficulty from the save as a whole.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Seat {
+    pub name: String,
+    pub human: bool,
+    /// The AI's personality, if it has one.
+    pub personality: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub rules: RuleSet,
+    pub difficulty: AIDifficulty,
+    pub seats: Vec<Seat>,
+    #[serde(flatten)]
+    pub position: Position,
+}
+
+/// Where everything kept between runs goes: saves, profiles and so on.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir().unwrap_or_default().join("uno_cli")
+}
+
+/// Where `save`, `load` and the autosave on Ctrl-C go when no file is given.
+pub fn default_path() -> PathBuf {
+    data_dir().join("saved-game.json")
+}
+
+impl SavedGame {
+    fn to_json(&self) -> Value {
+        let mut value = serde_json::to_value(self).expect("a save can always be written as JSON");
+        value["format"] = json!(FORMAT);
+        value["version"] = json!(VERSION);
+        value
+    }
+
+    fn from_json(value: &Value) -> Result<SavedGame, String> {
+        if value["format"] != FORMAT {
+            return Err("this is not a saved game".to_string());
+        }
+
+        if value["version"].as_u64() != Some(VERSION) {
+            return Err(format!("this save is version {}; only version {VERSION} can be loaded", value["version"]));
+        }
+
+        let saved = SavedGame::deserialize(value).map_err(|e| e.to_string())?;
+        let (seats, position) = (&saved.seats, &saved.position);
+
+        if seats.len() < 2 {
+            return Err(format!("a game needs at least 2 seats, not {}", seats.len()));
+        }
+
+        if position.hands.len() != seats.len() {
+            return Err(format!("there are {} hands for {} seats", position.hands.len(), seats.len()));
+        }
+
+        if position.to_move >= seats.len() {
+            return Err(format!("seat {} is to move, but there are only {} seats", position.to_move, seats.len()));
+        }
+
+        // The next player matches against the top card's color, so a wild on top must have one chosen.
+        match position.discard.last() {
+            None => return Err("the discard pile is empty".to_string()),
+            Some(card) if card.color().is_none() => {
+                return Err("the wild card on top of the discard pile has no color".to_string());
+            }
+            Some(_) => {}
+        }
+
+        if let Some(seat) = position.hands.iter().position(Vec::is_empty) {
+            return Err(format!("{} has no cards left, so the game is already over", seats[seat].name));
+        }
+
+        Ok(saved)
+    }
+
+    pub fn load(path: &Path) -> Result<SavedGame, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        let value = serde_json::from_str(&text).map_err(|e| format!("{} is not valid JSON: {e}", path.display()))?;
+
+        SavedGame::from_json(&value).map_err(|e| format!("Could not load {}: {e}.", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, format!("{:#}\n", self.to_json()))
+    }
+}
+
+/// The game being played right now, as far as saving it is concerned.
+pub struct CurrentGame {
+    pub rules: RuleSet,
+    pub difficulty: AIDifficulty,
+    pub seats: Vec<Seat>,
+    pub checkpoint: Checkpoint,
+}
+
+/// Makes `game` the one [`save`] writes out. There's only ever one per run; later games reuse its checkpoint.
+pub fn set_current(game: CurrentGame) {
+    let _ = CURRENT.set(game);
+}
+
+/// Writes the current game to `path`. Fails if no game is under way.
+pub fn save(path: &Path) -> Result<(), String> {
+    let current = CURRENT.get().ok_or("There's no game to save.")?;
+    let position = current.checkpoint.lock().unwrap().clone().ok_or("There's no game to save.")?;
+
+    let game = SavedGame {
+        rules: current.rules,
+        difficulty: current.difficulty,
+        seats: current.seats.clone(),
+        position,
+    };
+
+    game.write(path).map_err(|e| format!("Could not save to {}: {e}", path.display()))
+}
+
+/// Saves to the default file on the way out after Ctrl-C, if there's a game to save.
+pub fn autosave() {
+    let path = default_path();
+
+    if save(&path).is_ok() {
+        println!();
+        println!("Saved the game to {}. Start with load to continue it.", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{Card, CardColor, Deck};
+    use crate::game::Direction;
+
+    fn game() -> SavedGame {
+        let mut deck = Deck::generate().cards;
+        let hands = (0..3).map(|_| deck.split_off(deck.len() - 7)).collect();
+        let mut wild = Card::Wild { color: None };
+        wild.with_color(CardColor::Blue);
+
+        SavedGame {
+            rules: RuleSet::default(),
+            difficulty: AIDifficulty::Hard,
+            seats: vec![
+                Seat { name: "Rin".to_string(), human: false, personality: Some("bluffer".to_string()) },
+                Seat { name: "Sam".to_string(), human: true, personality: None },
+                Seat { name: "Rem".to_string(), human: false, personality: None },
+            ],
+            position: Position {
+                discard: vec![deck.pop().unwrap(), wild],
+                deck,
+                hands,
+                to_move: 2,
+                direction: Direction::CounterClockwise,
+                to_draw: 2,
+            },
+        }
+    }
+
+    fn notation(cards: &[Card]) -> Vec<String> {
+        cards.iter().map(Card::notation).collect()
+    }
+
+    fn hands(position: &Position) -> Vec<Vec<String>> {
+        position.hands.iter().map(|hand| notation(hand)).collect()
+    }
+
+    #[test]
+    fn round_trips_a_position() {
+        let saved = game();
+        let loaded = SavedGame::from_json(&saved.to_json()).unwrap();
+        let (before, after) = (&saved.position, &loaded.position);
+
+        // Cards are compared by notation, since equality ignores the color chosen for a wild.
+        assert_eq!(notation(&after.deck), notation(&before.deck));
+        assert_eq!(notation(&after.discard), notation(&before.discard));
+        assert_eq!(hands(after), hands(before));
+        assert_eq!((after.to_move, after.direction, after.to_draw), (before.to_move, before.direction, before.to_draw));
+        assert_eq!(loaded.rules, saved.rules);
+        assert_eq!(loaded.difficulty.to_string(), saved.difficulty.to_string());
+        assert_eq!(format!("{:?}", loaded.seats), format!("{:?}", saved.seats));
+        assert_eq!(loaded.to_json(), saved.to_json());
+    }
+
+    fn rejects(change: impl FnOnce(&mut Value), error: &str) {
+        let mut value = game().to_json();
+        change(&mut value);
+
+        match SavedGame::from_json(&value) {
+            Ok(_) => panic!("loaded a save where {error}"),
+            Err(e) => assert!(e.contains(error), "{e}"),
+        }
+    }
+
+    #[test]
+    fn rejects_a_hand_count_that_does_not_match_the_seats() {
+        rejects(|value| value["hands"].as_array_mut().unwrap().truncate(2), "there are 2 hands for 3 seats");
+    }
+
+    #[test]
+    fn rejects_an_empty_discard_pile() {
+        rejects(|value| value["discard"] = json!([]), "the discard pile is empty");
+    }
+
+    #[test]
+    fn rejects_an_uncolored_wild_on_top() {
+        rejects(|value| value["discard"][1] = json!("+4"), "the wild card on top of the discard pile has no color");
+    }
+
+    #[test]
+    fn rejects_fewer_than_two_seats() {
+        rejects(
+            |value| {
+                value["seats"].as_array_mut().unwrap().truncate(1);
+                value["hands"].as_array_mut().unwrap().truncate(1);
+                value["to_move"] = json!(0);
+            },
+            "a game needs at least 2 seats, not 1",
+        );
+    }
+
+    #[test]
+    fn rejects_a_game_that_is_already_won() {
+        rejects(|value| value["hands"][1] = json!([]), "Sam has no cards left, so the game is already over");
+    }
+
+    #[test]
+    fn rejects_a_player_to_move_out_of_range() {
+        rejects(|value| value["to_move"] = json!(3), "seat 3 is to move, but there are only 3 seats");
+    }
+}
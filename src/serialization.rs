@@ -0,0 +1,378 @@
+//! Serde support for the card and game model. Saves and game records are written with it too.
+//!
+//! The representations are meant to be stored and sent over the wire, so they're kept stable:
+//!
+//! - `CardColor` is its lowercase name, e.g. `"red"`; single letters are accepted too.
+//! - `CardValue` is its number, 0 to 9.
+//! - `Card` is its notation, e.g. `"R5"`, `"GS"`, `"B+2"` or `"W:Y"`, so a wild card keeps the color chosen for it.
+//! - `AIDifficulty` is its name, e.g. `"Hard"`, and `Direction` is `"clockwise"` or `"counterclockwise"`.
+//! - `TurnResult` is `{"type":"played","card":"R5"}` or `{"type":"drew"}`.
+//! - `GameEvent` is tagged the same way, e.g. `{"type":"drew","player":2,"count":4}` or
+//!   `{"type":"turn_started","player":0}`, with players as seat numbers.
+//! - `Deck` and `Turn` are objects with a `version` field, currently [`VERSION`]. Anything else is refused, rather
+//!   than read as something it isn't.
+//! - `Position` is an object with `deck`, `discard`, `hands`, `to_move`, `direction` and `to_draw`. It has no version
+//!   of its own, since it's stored as part of a save, which does.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::card::{Card, CardColor, CardValue, Deck};
+use crate::game::{Direction, GameEvent, Position, Turn, TurnResult};
+use crate::player::AIDifficulty;
+use crate::rules::RuleSet;
+
+pub const VERSION: u32 = 1;
+
+impl Serialize for CardColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string().to_lowercase())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for CardValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.number())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let number = u8::deserialize(deserializer)?;
+
+        CardValue::from_number(number).ok_or_else(|| D::Error::custom(format!("{number} is not a card value")))
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.notation())
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for AIDifficulty {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for AIDifficulty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Direction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            Direction::Clockwise => "clockwise",
+            Direction::CounterClockwise => "counterclockwise",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Direction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "clockwise" => Ok(Direction::Clockwise),
+            "counterclockwise" => Ok(Direction::CounterClockwise),
+            other => Err(D::Error::custom(format!("{other} is not a direction of play"))),
+        }
+    }
+}
+
+fn check_version<E: Error>(version: u32) -> Result<(), E> {
+    if version == VERSION {
+        Ok(())
+    } else {
+        Err(E::custom(format!("version {version} is not supported; only version {VERSION} is")))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeckV1 {
+    version: u32,
+    /// Top of the deck last.
+    cards: Vec<Card>,
+}
+
+impl Serialize for Deck {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DeckV1 { version: VERSION, cards: self.cards.clone() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Deck {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let deck = DeckV1::deserialize(deserializer)?;
+        check_version(deck.version)?;
+
+        Ok(Deck { cards: deck.cards })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TurnV1 {
+    version: u32,
+    to_draw: u8,
+    full_hand: Vec<Card>,
+    playable_hand: Vec<Card>,
+    last_card: Card,
+    rules: RuleSet,
+    opponents: Vec<usize>,
+}
+
+impl Serialize for Turn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TurnV1 {
+            version: VERSION,
+            to_draw: self.to_draw,
+            full_hand: self.full_hand.clone(),
+            playable_hand: self.playable_hand.clone(),
+            last_card: self.last_card,
+            rules: self.rules,
+            opponents: self.opponents.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Turn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let turn = TurnV1::deserialize(deserializer)?;
+        check_version(turn.version)?;
+
+        Ok(Turn {
+            to_draw: turn.to_draw,
+            full_hand: turn.full_hand,
+            playable_hand: turn.playable_hand,
+            last_card: turn.last_card,
+            rules: turn.rules,
+            opponents: turn.opponents,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TurnResultV1 {
+    Played { card: Card },
+    Drew,
+}
+
+impl Serialize for TurnResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            TurnResult::Played(card) => TurnResultV1::Played { card },
+            TurnResult::Drew => TurnResultV1::Drew,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TurnResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match TurnResultV1::deserialize(deserializer)? {
+            TurnResultV1::Played { card } => TurnResult::Played(card),
+            TurnResultV1::Drew => TurnResult::Drew,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GameEventV1 {
+    Started { players: Vec<String>, top_card: Card },
+    TurnStarted { player: usize },
+    Played { player: usize, card: Card },
+    Drew { player: usize, count: usize },
+    Skipped { player: usize },
+    Won { player: usize },
+}
+
+impl Serialize for GameEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.clone() {
+            GameEvent::Started { players, top_card } => GameEventV1::Started { players, top_card },
+            GameEvent::TurnStarted { player } => GameEventV1::TurnStarted { player },
+            GameEvent::Played { player, card } => GameEventV1::Played { player, card },
+            GameEvent::Drew { player, count } => GameEventV1::Drew { player, count },
+            GameEvent::Skipped { player } => GameEventV1::Skipped { player },
+            GameEvent::Won { player } => GameEventV1::Won { player },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match GameEventV1::deserialize(deserializer)? {
+            GameEventV1::Started { players, top_card } => GameEvent::Started { players, top_card },
+            GameEventV1::TurnStarted { player } => GameEvent::TurnStarted { player },
+            GameEventV1::Played { player, card } => GameEvent::Played { player, card },
+            GameEventV1::Drew { player, count } => GameEvent::Drew { player, count },
+            GameEventV1::Skipped { player } => GameEvent::Skipped { player },
+            GameEventV1::Won { player } => GameEvent::Won { player },
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PositionV1 {
+    deck: Vec<Card>,
+    discard: Vec<Card>,
+    hands: Vec<Vec<Card>>,
+    to_move: usize,
+    direction: Direction,
+    to_draw: u8,
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PositionV1 {
+            deck: self.deck.clone(),
+            discard: self.discard.clone(),
+            hands: self.hands.clone(),
+            to_move: self.to_move,
+            direction: self.direction,
+            to_draw: self.to_draw,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let position = PositionV1::deserialize(deserializer)?;
+
+        Ok(Position {
+            deck: position.deck,
+            discard: position.discard,
+            hands: position.hands,
+            to_move: position.to_move,
+            direction: position.direction,
+            to_draw: position.to_draw,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+    }
+
+    fn red_five() -> Card {
+        Card::Numeric { color: CardColor::Red, value: CardValue::Five }
+    }
+
+    #[test]
+    fn cards_keep_the_color_chosen_for_a_wild() {
+        for (card, notation) in [
+            (red_five(), "R5"),
+            (Card::Wild { color: None }, "W"),
+            (Card::Wild { color: Some(CardColor::Yellow) }, "W:Y"),
+            (Card::DrawFour { color: None }, "+4"),
+            (Card::DrawFour { color: Some(CardColor::Green) }, "+4:G"),
+        ] {
+            assert_eq!(serde_json::to_value(card).unwrap(), json!(notation));
+
+            // Equality ignores a wild's color, so compare that separately.
+            let read = round_trip(&card);
+            assert_eq!((read, read.color()), (card, card.color()), "{notation}");
+        }
+
+        for card in Deck::generate().cards {
+            assert_eq!(round_trip(&card).notation(), card.notation());
+        }
+    }
+
+    #[test]
+    fn game_events_round_trip() {
+        let events = [
+            (GameEvent::Started { players: vec!["Rin".to_string(), "Sam".to_string()], top_card: red_five() }, json!({ "type": "started", "players": ["Rin", "Sam"], "top_card": "R5" })),
+            (GameEvent::TurnStarted { player: 1 }, json!({ "type": "turn_started", "player": 1 })),
+            (GameEvent::Played { player: 0, card: Card::Wild { color: Some(CardColor::Red) } }, json!({ "type": "played", "player": 0, "card": "W:R" })),
+            (GameEvent::Played { player: 1, card: Card::DrawFour { color: Some(CardColor::Blue) } }, json!({ "type": "played", "player": 1, "card": "+4:B" })),
+            (GameEvent::Drew { player: 1, count: 4 }, json!({ "type": "drew", "player": 1, "count": 4 })),
+            (GameEvent::Skipped { player: 0 }, json!({ "type": "skipped", "player": 0 })),
+            (GameEvent::Won { player: 1 }, json!({ "type": "won", "player": 1 })),
+        ];
+
+        for (event, expected) in events {
+            assert_eq!(serde_json::to_value(&event).unwrap(), expected);
+
+            // Through the Debug output, so wild colors are compared too.
+            assert_eq!(format!("{:?}", round_trip(&event)), format!("{event:?}"));
+        }
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let position = Position {
+            deck: vec![red_five(), Card::Skip { color: CardColor::Green }],
+            discard: vec![Card::Reverse { color: CardColor::Blue }, Card::DrawFour { color: Some(CardColor::Yellow) }],
+            hands: vec![vec![Card::Wild { color: None }], vec![Card::DrawTwo { color: CardColor::Red }, red_five()]],
+            to_move: 1,
+            direction: Direction::CounterClockwise,
+            to_draw: 4,
+        };
+
+        let value = serde_json::to_value(&position).unwrap();
+        assert_eq!(value["direction"], "counterclockwise");
+        assert_eq!(value["discard"], json!(["BR", "+4:Y"]));
+        assert_eq!(format!("{:?}", round_trip(&position)), format!("{position:?}"));
+    }
+
+    #[test]
+    fn simple_values_round_trip() {
+        for direction in [Direction::Clockwise, Direction::CounterClockwise] {
+            assert_eq!(round_trip(&direction), direction);
+        }
+
+        for difficulty in [AIDifficulty::Easy, AIDifficulty::Medium, AIDifficulty::Hard] {
+            assert_eq!(round_trip(&difficulty), difficulty);
+        }
+
+        assert_eq!(serde_json::to_value(AIDifficulty::Hard).unwrap(), json!("Hard"));
+        assert_eq!(serde_json::to_value(TurnResult::Drew).unwrap(), json!({ "type": "drew" }));
+        assert!(matches!(round_trip(&TurnResult::Played(red_five())), TurnResult::Played(card) if card == red_five()));
+    }
+
+    #[test]
+    fn refuses_other_versions() {
+        let mut deck = serde_json::to_value(Deck::generate()).unwrap();
+        assert_eq!(deck["version"], VERSION);
+
+        deck["version"] = json!(VERSION + 1);
+        let error = serde_json::from_value::<Deck>(deck).err().unwrap().to_string();
+        assert!(error.starts_with(&format!("version {} is not supported", VERSION + 1)), "{error}");
+    }
+
+    #[test]
+    fn refuses_values_it_does_not_know() {
+        let error = serde_json::from_value::<Direction>(json!("sideways")).err().unwrap();
+        assert_eq!(error.to_string(), "sideways is not a direction of play");
+
+        assert!(serde_json::from_value::<Card>(json!("R10")).is_err());
+        assert!(serde_json::from_value::<Card>(json!("W:")).is_err());
+        assert!(serde_json::from_value::<GameEvent>(json!({ "type": "jumped", "player": 0 })).is_err());
+        assert!(serde_json::from_value::<GameEvent>(json!({ "type": "won" })).is_err());
+    }
+}
@@ -0,0 +1,800 @@
+//! Embedded HTTP/WebSocket lobby server.
+//!
+//! `GET /` serves a small HTML table, and `GET /ws` upgrades to a WebSocket carrying JSON messages.
+//! A client introduces itself with `{"type": "hello", "name": "..."}`, then uses `list`, `create`, `join`,
+//! `leave`, `ready` and `start` to get into a room. Each room runs its game on its own thread. On its turn a
+//! client receives a `turn` message and answers with `{"type": "play", "card": <hand index>, "color": "red"}`
+//! or `{"type": "draw"}`. Rooms created with `turn_seconds` give each client that long to answer, after which
+//! the seat draws or an AI stand-in plays for it (`"fallback": "draw"` or `"ai"`) and the client gets a `timeout`
+//! message. Every public game event is streamed as it happens.
+//!
+//! `{"type": "watch", "room": <id>}` joins a room as a spectator instead, even mid-game. Spectators get the public
+//! events plus a `table` snapshot after each one, which only includes everyone's hands if the room was created
+//! with `"open_hands": true`.
+//!
+//! A seated client can `leave` mid-game, after which its seat draws every turn; once every seated client has left or
+//! disconnected, the game is stopped and the room goes with it.
+//!
+//! Finished games count towards the online profiles, which the lobby thread keeps, so rooms finishing at the same
+//! time don't write over each other. With the `history` feature they go in the match history too.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::AssertUnwindSafe;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+use crate::card::{Card, CardColor};
+use crate::deadline::{Deadline, Fallback};
+use crate::game::{Direction, GameEvent, GameObserver, GameState, Stop, Table, Turn, TurnResult};
+#[cfg(feature = "history")]
+use crate::history::MatchLogger;
+use crate::player::{AIDifficulty, Ai, AsyncPlayer, Player};
+use crate::profile::{Outcome, ProfileTracker, Profiles};
+use crate::rules::RuleSet;
+use crate::save::Seat;
+
+const INDEX_HTML: &str = include_str!("../web/index.html");
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+const MAX_SEATS: usize = 8;
+
+/// How long a client's socket waits for something to read before checking for something to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+static NEXT_CLIENT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn run(address: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+
+    // The port actually bound, in case it was left to the system with port 0.
+    println!("Serving Uno on http://{}", listener.local_addr()?);
+
+    let (lobby, inbox) = channel();
+    let lobby_handle = lobby.clone();
+
+    let profiles = Profiles::load_online().unwrap_or_else(|e| {
+        println!("{e}");
+        Profiles::default()
+    });
+
+    std::thread::spawn(move || Lobby { profiles, ..Lobby::default() }.run(inbox, lobby_handle));
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let lobby = lobby.clone();
+                std::thread::spawn(move || handle_connection(stream, lobby));
+            }
+            Err(e) => println!("Failed to accept connection: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, lobby: Sender<LobbyInput>) {
+    let mut buffer = [0; 16];
+    let read = stream.peek(&mut buffer).unwrap_or(0);
+
+    if buffer[..read].starts_with(b"GET /ws") {
+        match tungstenite::accept(stream) {
+            Ok(socket) => serve_client(socket, lobby),
+            Err(e) => println!("WebSocket handshake failed: {e}"),
+        }
+
+        return;
+    }
+
+    // Drain the request; anything that isn't the socket gets the page.
+    let mut request = [0; 4096];
+    let _ = stream.read(&mut request);
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{INDEX_HTML}",
+        INDEX_HTML.len()
+    );
+
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Pumps one client's socket, forwarding everything it reads to the lobby and sending everything in its outbox. Only
+/// this thread touches the socket, so frames going each way can't get mixed up; reads time out every
+/// [`POLL_INTERVAL`] to let the outbox through.
+fn serve_client(mut socket: WebSocket<TcpStream>, lobby: Sender<LobbyInput>) {
+    let id = NEXT_CLIENT.fetch_add(1, Ordering::Relaxed);
+    let (outbox, outgoing) = channel::<Value>();
+
+    if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+        println!("Could not set up client socket: {e}");
+        return;
+    }
+
+    if lobby.send(LobbyInput::Connected(id, outbox.clone())).is_err() {
+        return;
+    }
+
+    'client: loop {
+        while let Ok(message) = outgoing.try_recv() {
+            if socket.send(Message::text(message.to_string())).is_err() {
+                break 'client;
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(text.as_str()) {
+                Ok(value) => {
+                    if lobby.send(LobbyInput::Message(id, value)).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    let _ = outbox.send(error_json("Messages must be JSON."));
+                }
+            },
+            // Nothing to read yet. Tungstenite keeps any partial frame for the next read.
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+
+    let _ = lobby.send(LobbyInput::Disconnected(id));
+}
+
+enum LobbyInput {
+    Connected(usize, Sender<Value>),
+    Message(usize, Value),
+    Disconnected(usize),
+    /// A room's game is over: who won, and how it went for the profiles if it got as far as a winner.
+    Finished(usize, String, Option<Outcome>),
+}
+
+struct Client {
+    name: Option<String>,
+    outbox: Sender<Value>,
+    room: Option<usize>,
+    ready: bool,
+    /// Set while the client is seated in a running game; its play and draw messages go here.
+    seat: Option<Sender<Value>>,
+}
+
+struct Room {
+    name: String,
+    host: usize,
+    members: Vec<usize>,
+    spectators: Vec<usize>,
+    /// Outboxes of the spectators, shared with the running game's [`SpectatorFeed`].
+    feed: Arc<Mutex<HashMap<usize, Sender<Value>>>>,
+    open_hands: bool,
+    ai_players: usize,
+    difficulty: AIDifficulty,
+    rules: RuleSet,
+    turn_limit: Option<Duration>,
+    ai_fallback: bool,
+    playing: bool,
+    /// Stops the running game, once nobody is left in its seats.
+    stop: Option<Stop>,
+}
+
+#[derive(Default)]
+struct Lobby {
+    clients: HashMap<usize, Client>,
+    rooms: BTreeMap<usize, Room>,
+    next_room: usize,
+    /// The online profiles as of the last game to finish, for showing ratings.
+    profiles: Profiles,
+}
+
+impl Lobby {
+    fn run(mut self, inbox: Receiver<LobbyInput>, handle: Sender<LobbyInput>) {
+        for input in inbox {
+            match input {
+                LobbyInput::Connected(id, outbox) => {
+                    self.clients.insert(id, Client { name: None, outbox, room: None, ready: false, seat: None });
+                }
+                LobbyInput::Message(id, message) => {
+                    if let Err(e) = self.handle(id, &message, &handle) {
+                        self.send(id, error_json(&e));
+                    }
+                }
+                LobbyInput::Disconnected(id) => {
+                    self.leave_room(id);
+                    self.clients.remove(&id);
+                }
+                LobbyInput::Finished(room_id, winner, outcome) => {
+                    if let Some(outcome) = outcome {
+                        self.record(outcome);
+                    }
+
+                    self.finish(room_id, winner);
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, id: usize, message: &Value, handle: &Sender<LobbyInput>) -> Result<(), String> {
+        let client = self.clients.get(&id).ok_or("Unknown client.")?;
+
+        // Everything but leaving goes to the seat while its game is on.
+        if let (Some(seat), false) = (&client.seat, message["type"] == "leave") {
+            let _ = seat.send(message.clone());
+            return Ok(());
+        }
+
+        match message["type"].as_str() {
+            Some("hello") => {
+                let name = message["name"].as_str().unwrap_or_default().trim();
+                let name = if name.is_empty() { "Guest".to_string() } else { name.to_string() };
+
+                self.clients.get_mut(&id).unwrap().name = Some(name);
+                self.send(id, self.room_list());
+            }
+            _ if client.name.is_none() => return Err("Say hello with your name first.".to_string()),
+            Some("list") => self.send(id, self.room_list()),
+            Some("create") => {
+                let room = Room {
+                    name: message["name"].as_str().unwrap_or("Uno table").to_string(),
+                    host: id,
+                    members: vec![],
+                    spectators: vec![],
+                    feed: Arc::default(),
+                    open_hands: message["open_hands"].as_bool().unwrap_or(false),
+                    ai_players: message["ai_players"].as_u64().unwrap_or(3) as usize,
+                    difficulty: message["difficulty"].as_str().map_or(Ok(AIDifficulty::Medium), AIDifficulty::from_str)?,
+                    rules: rules_from_json(&message["rules"])?,
+                    turn_limit: message["turn_seconds"].as_u64().filter(|s| *s > 0).map(Duration::from_secs),
+                    ai_fallback: match message["fallback"].as_str() {
+                        None | Some("ai") => true,
+                        Some("draw") => false,
+                        Some(other) => return Err(format!("{other} is not a valid fallback")),
+                    },
+                    playing: false,
+                    stop: None,
+                };
+
+                if room.ai_players >= MAX_SEATS {
+                    return Err(format!("A room seats at most {MAX_SEATS} players."));
+                }
+
+                self.leave_room(id);
+
+                let room_id = self.next_room;
+                self.next_room += 1;
+                self.rooms.insert(room_id, room);
+
+                if message["watch"].as_bool().unwrap_or(false) {
+                    self.watch_room(id, room_id)?;
+                } else {
+                    self.join_room(id, room_id)?;
+                }
+            }
+            Some("join") => {
+                let room_id = message["room"].as_u64().ok_or("Which room?")? as usize;
+
+                self.leave_room(id);
+                self.join_room(id, room_id)?;
+            }
+            Some("watch") => {
+                let room_id = message["room"].as_u64().ok_or("Which room?")? as usize;
+
+                self.leave_room(id);
+                self.watch_room(id, room_id)?;
+            }
+            Some("leave") => {
+                self.leave_room(id);
+                self.send(id, self.room_list());
+            }
+            Some("ready") => {
+                let room_id = client.room.ok_or("You aren't in a room.")?;
+
+                self.clients.get_mut(&id).unwrap().ready = message["ready"].as_bool().unwrap_or(true);
+                self.broadcast_room(room_id);
+            }
+            Some("start") => {
+                let room_id = client.room.ok_or("You aren't in a room.")?;
+                self.start(id, room_id, handle)?;
+            }
+            _ => return Err("Unknown message type.".to_string()),
+        }
+
+        Ok(())
+    }
+
+    fn join_room(&mut self, id: usize, room_id: usize) -> Result<(), String> {
+        let room = self.rooms.get_mut(&room_id).ok_or("That room doesn't exist.")?;
+
+        if room.playing {
+            return Err("That game has already started.".to_string());
+        }
+
+        if room.members.len() + room.ai_players >= MAX_SEATS {
+            return Err("That room is full.".to_string());
+        }
+
+        room.members.push(id);
+
+        let client = self.clients.get_mut(&id).unwrap();
+        client.room = Some(room_id);
+        client.ready = false;
+
+        self.broadcast_room(room_id);
+        Ok(())
+    }
+
+    fn watch_room(&mut self, id: usize, room_id: usize) -> Result<(), String> {
+        let room = self.rooms.get_mut(&room_id).ok_or("That room doesn't exist.")?;
+        let client = self.clients.get_mut(&id).unwrap();
+
+        room.spectators.push(id);
+        room.feed.lock().unwrap().insert(id, client.outbox.clone());
+
+        client.room = Some(room_id);
+        client.ready = false;
+
+        self.broadcast_room(room_id);
+        Ok(())
+    }
+
+    fn leave_room(&mut self, id: usize) {
+        let Some(client) = self.clients.get_mut(&id) else { return };
+        let Some(room_id) = client.room.take() else { return };
+
+        client.ready = false;
+        client.seat = None;
+
+        let Some(room) = self.rooms.get_mut(&room_id) else { return };
+        let seated = room.members.contains(&id);
+        room.members.retain(|m| *m != id);
+        room.spectators.retain(|s| *s != id);
+        room.feed.lock().unwrap().remove(&id);
+
+        // The lobby hears about the stopped game once it's over, and drops the room then.
+        if let (true, true, Some(stop)) = (seated, room.members.is_empty(), &room.stop) {
+            stop.store(true, Ordering::SeqCst);
+        }
+
+        if room.members.is_empty() && room.spectators.is_empty() && !room.playing {
+            self.rooms.remove(&room_id);
+            return;
+        }
+
+        if room.host == id {
+            if let Some(host) = room.members.first().or(room.spectators.first()) {
+                room.host = *host;
+            }
+        }
+
+        self.broadcast_room(room_id);
+    }
+
+    fn start(&mut self, id: usize, room_id: usize, handle: &Sender<LobbyInput>) -> Result<(), String> {
+        let room = self.rooms.get_mut(&room_id).ok_or("That room doesn't exist.")?;
+
+        if room.host != id {
+            return Err("Only the host can start the game.".to_string());
+        }
+
+        if room.playing {
+            return Err("The game has already started.".to_string());
+        }
+
+        if room.members.len() + room.ai_players < 2 {
+            return Err("A game needs at least two players.".to_string());
+        }
+
+        if room.members.iter().any(|m| !self.clients[m].ready) {
+            return Err("Everyone has to be ready first.".to_string());
+        }
+
+        room.playing = true;
+
+        let stop = Stop::new(AtomicBool::new(false));
+        room.stop = Some(stop.clone());
+
+        let mut players: Vec<Box<dyn Player>> = vec![];
+
+        for member in room.members.iter() {
+            let client = self.clients.get_mut(member).unwrap();
+            let (seat, inbox) = channel();
+
+            client.seat = Some(seat);
+
+            let remote = RemotePlayer {
+                name: client.name.clone().unwrap_or_default(),
+                inbox,
+                outbox: client.outbox.clone(),
+                connected: true,
+                turn: None,
+            };
+
+            let fallback = if room.ai_fallback {
+                Fallback::StandIn(Box::new(Ai::new(StdRng::from_entropy(), room.difficulty)))
+            } else {
+                Fallback::Draw
+            };
+
+            players.push(Box::new(Deadline::new(remote, room.turn_limit, fallback)));
+        }
+
+        for _ in 0..room.ai_players {
+            players.push(Box::new(Ai::new(StdRng::from_entropy(), room.difficulty)));
+        }
+
+        let seats = players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Seat { name: p.name().to_string(), human: i < room.members.len(), personality: None })
+            .collect::<Vec<Seat>>();
+        let (outcomes, outcome) = channel();
+        let mut tracker = ProfileTracker::new(&seats, (room.ai_players > 0).then_some(room.difficulty));
+        tracker.send_outcome(outcomes);
+        let rules = room.rules;
+        let handle = handle.clone();
+        let feed = SpectatorFeed { outboxes: room.feed.clone(), open_hands: room.open_hands };
+        #[cfg(feature = "history")]
+        let difficulty = (room.ai_players > 0).then_some(room.difficulty);
+
+        std::thread::spawn(move || {
+            let mut game = GameState::new(players, rules);
+            game.set_stop(stop);
+            game.add_observer(Box::new(feed));
+            game.add_observer(Box::new(tracker));
+            #[cfg(feature = "history")]
+            game.add_observer(Box::new(MatchLogger::new(&seats, difficulty, rules, Some(game.seed()))));
+
+            // A panicking seat shouldn't leave the room stuck mid-game forever.
+            let winner = std::panic::catch_unwind(AssertUnwindSafe(|| game.start())).ok().flatten().unwrap_or_else(|| "Nobody".to_string());
+            let _ = handle.send(LobbyInput::Finished(room_id, winner, outcome.try_recv().ok()));
+        });
+
+        self.broadcast_room(room_id);
+        Ok(())
+    }
+
+    /// Adds a game to the online profiles. They're read afresh first, in case they were changed by hand.
+    fn record(&mut self, outcome: Outcome) {
+        let mut profiles = match Profiles::load_online() {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                println!("{e}");
+                return;
+            }
+        };
+
+        outcome.apply(&mut profiles);
+
+        if let Err(e) = profiles.save_online() {
+            println!("{e}");
+        }
+
+        self.profiles = profiles;
+    }
+
+    fn finish(&mut self, room_id: usize, winner: String) {
+        let Some(room) = self.rooms.get_mut(&room_id) else { return };
+        let stopped = room.stop.take().is_some_and(|stop| stop.load(Ordering::SeqCst));
+        room.playing = false;
+
+        for member in room.members.iter().chain(room.spectators.iter()) {
+            if let Some(client) = self.clients.get_mut(member) {
+                client.seat = None;
+                client.ready = false;
+                let _ = client.outbox.send(json!({ "type": "game_over", "winner": winner }));
+            }
+        }
+
+        let abandoned = stopped || (room.members.is_empty() && room.spectators.is_empty());
+
+        if !abandoned {
+            self.broadcast_room(room_id);
+            return;
+        }
+
+        // Everyone seated left, so whoever was watching goes back to the lobby.
+        for spectator in self.rooms.remove(&room_id).map(|room| room.spectators).unwrap_or_default() {
+            if let Some(client) = self.clients.get_mut(&spectator) {
+                client.room = None;
+            }
+
+            self.send(spectator, self.room_list());
+        }
+    }
+
+    fn send(&self, id: usize, message: Value) {
+        if let Some(client) = self.clients.get(&id) {
+            let _ = client.outbox.send(message);
+        }
+    }
+
+    fn broadcast_room(&self, room_id: usize) {
+        let Some(room) = self.rooms.get(&room_id) else { return };
+        let mut message = json!({ "type": "room", "id": room_id, "room": self.room_json(room) });
+
+        for member in room.members.iter().chain(room.spectators.iter()) {
+            message["is_host"] = json!(room.host == *member);
+            message["watching"] = json!(room.spectators.contains(member));
+            self.send(*member, message.clone());
+        }
+    }
+
+    fn room_list(&self) -> Value {
+        let rooms = self.rooms
+            .iter()
+            .map(|(id, room)| {
+                let mut value = self.room_json(room);
+                value["id"] = json!(id);
+                value
+            })
+            .collect::<Vec<Value>>();
+
+        json!({ "type": "rooms", "rooms": rooms })
+    }
+
+    fn room_json(&self, room: &Room) -> Value {
+        let profiles = &self.profiles;
+        let members = room.members
+            .iter()
+            .filter_map(|m| self.clients.get(m))
+            .map(|c| {
+                let rating = profiles.rating(c.name.as_deref().unwrap_or_default()).round();
+                json!({ "name": c.name, "ready": c.ready, "rating": rating })
+            })
+            .collect::<Vec<Value>>();
+
+        let spectators = room.spectators
+            .iter()
+            .filter_map(|s| self.clients.get(s))
+            .map(|c| c.name.clone())
+            .collect::<Vec<Option<String>>>();
+
+        json!({
+            "name": room.name,
+            "host": self.clients.get(&room.host).and_then(|c| c.name.clone()),
+            "members": members,
+            "spectators": spectators,
+            "open_hands": room.open_hands,
+            "ai_players": room.ai_players,
+            "difficulty": room.difficulty.to_string(),
+            "difficulty_rating": profiles.ai_rating(room.difficulty).round(),
+            "rules": rules_json(&room.rules),
+            "turn_seconds": room.turn_limit.map(|t| t.as_secs()),
+            "fallback": if room.ai_fallback { "ai" } else { "draw" },
+            "playing": room.playing,
+        })
+    }
+}
+
+/// Streams a running game to a room's spectators, with everyone's hands if the room allows it.
+struct SpectatorFeed {
+    outboxes: Arc<Mutex<HashMap<usize, Sender<Value>>>>,
+    open_hands: bool,
+}
+
+impl GameObserver for SpectatorFeed {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let mut snapshot = json!({
+            "type": "table",
+            "players": table.names,
+            "hand_sizes": table.hands.iter().map(|h| h.len()).collect::<Vec<usize>>(),
+            "top_card": table.top_card.as_ref().map(card_json),
+            "current_player": table.current_player,
+            "direction": match table.direction {
+                Direction::Clockwise => "clockwise",
+                Direction::CounterClockwise => "counter_clockwise",
+            },
+            "to_draw": table.to_draw,
+        });
+
+        if self.open_hands {
+            snapshot["hands"] = table.hands
+                .iter()
+                .map(|hand| hand.iter().map(card_json).collect::<Vec<Value>>())
+                .collect();
+        }
+
+        let event = event_json(event);
+
+        for outbox in self.outboxes.lock().unwrap().values() {
+            let _ = outbox.send(event.clone());
+            let _ = outbox.send(snapshot.clone());
+        }
+    }
+}
+
+/// A seat driven by a WebSocket client. If the client goes away, the seat keeps drawing until the game ends, or until
+/// the lobby stops it because nobody is left.
+pub struct RemotePlayer {
+    name: String,
+    inbox: Receiver<Value>,
+    outbox: Sender<Value>,
+    connected: bool,
+    turn: Option<Turn>,
+}
+
+impl RemotePlayer {
+    fn send(&mut self, message: Value) {
+        if self.connected && self.outbox.send(message).is_err() {
+            self.connected = false;
+        }
+    }
+
+    /// Throws away anything sent while it isn't our turn, noticing on the way if the client has left the seat.
+    fn discard_stale(&mut self) {
+        loop {
+            match self.inbox.try_recv() {
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncPlayer for RemotePlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn start_turn(&mut self, turn: &Turn) {
+        self.discard_stale();
+
+        let playable = turn.full_hand
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| turn.playable_hand.contains(c))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        self.send(json!({
+            "type": "turn",
+            "hand": turn.full_hand.iter().map(card_json).collect::<Vec<Value>>(),
+            "playable": playable,
+            "top_card": card_json(&turn.last_card),
+            "to_draw": turn.to_draw,
+        }));
+
+        self.turn = Some(turn.clone());
+    }
+
+    fn poll_turn(&mut self, timeout: Option<Duration>) -> Option<TurnResult> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+
+        loop {
+            let Some(turn) = &self.turn else { return Some(TurnResult::Drew) };
+
+            if !self.connected {
+                return Some(TurnResult::Drew);
+            }
+
+            let message = match deadline {
+                Some(deadline) => self.inbox.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self.inbox.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            let result = match message {
+                Ok(message) => match message["type"].as_str() {
+                    Some("draw") => Ok(TurnResult::Drew),
+                    Some("play") => parse_play(&message, turn),
+                    _ => Err("Expected a play or draw message.".to_string()),
+                },
+                Err(RecvTimeoutError::Timeout) => return None,
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.connected = false;
+                    Ok(TurnResult::Drew)
+                }
+            };
+
+            match result {
+                Ok(result) => {
+                    self.turn = None;
+                    return Some(result);
+                }
+                Err(e) => self.send(error_json(&e)),
+            }
+        }
+    }
+
+    fn cancel_turn(&mut self) {
+        self.turn = None;
+        self.send(json!({ "type": "timeout" }));
+    }
+
+    fn observe_turn_skip(&mut self, observed_cards: Option<Vec<&Card>>) {
+        self.discard_stale();
+
+        if let Some(cards) = observed_cards {
+            self.send(json!({ "type": "drawn", "cards": cards.into_iter().map(card_json).collect::<Vec<Value>>() }));
+        }
+    }
+
+    fn observe_event(&mut self, event: &GameEvent) {
+        self.discard_stale();
+        self.send(event_json(event));
+    }
+}
+
+fn parse_play(message: &Value, turn: &Turn) -> Result<TurnResult, String> {
+    let index = message["card"].as_u64().ok_or("The play message needs a card index.")? as usize;
+    let mut card = *turn.full_hand.get(index).ok_or(format!("There is no card at index {index}."))?;
+
+    if !turn.playable_hand.contains(&card) {
+        return Err(format!("{card} can't be played right now."));
+    }
+
+    if matches!(card, Card::Wild { .. } | Card::DrawFour { .. }) {
+        let color = message["color"].as_str().ok_or("Wild cards need a color.")?;
+        card.with_color(CardColor::from_str(color)?);
+    }
+
+    Ok(TurnResult::Played(card))
+}
+
+fn error_json(message: &str) -> Value {
+    json!({ "type": "error", "message": message })
+}
+
+fn rules_json(rules: &RuleSet) -> Value {
+    json!({ "hand_size": rules.hand_size, "stacking": rules.stacking })
+}
+
+fn rules_from_json(value: &Value) -> Result<RuleSet, String> {
+    let mut rules = RuleSet::default();
+
+    if let Some(hand_size) = value["hand_size"].as_u64() {
+        if !(1..=10).contains(&hand_size) {
+            return Err("Hand size must be between 1 and 10.".to_string());
+        }
+
+        rules.hand_size = hand_size as u8;
+    }
+
+    if let Some(stacking) = value["stacking"].as_bool() {
+        rules.stacking = stacking;
+    }
+
+    Ok(rules)
+}
+
+pub fn card_json(card: &Card) -> Value {
+    let (kind, value) = match card {
+        Card::Numeric { value, .. } => ("numeric", Some(value.number())),
+        Card::Skip { .. } => ("skip", None),
+        Card::Reverse { .. } => ("reverse", None),
+        Card::DrawTwo { .. } => ("draw_two", None),
+        Card::Wild { .. } => ("wild", None),
+        Card::DrawFour { .. } => ("draw_four", None),
+    };
+
+    json!({
+        "kind": kind,
+        "value": value,
+        "color": card.color().map(|c| c.to_string().to_lowercase()),
+        "name": card.to_string(),
+        "notation": card.notation(),
+    })
+}
+
+pub fn event_json(event: &GameEvent) -> Value {
+    match event {
+        GameEvent::Started { players, top_card } => json!({ "type": "started", "players": players, "top_card": card_json(top_card) }),
+        GameEvent::TurnStarted { player } => json!({ "type": "turn_started", "player": player }),
+        GameEvent::Played { player, card } => json!({ "type": "played", "player": player, "card": card_json(card) }),
+        GameEvent::Drew { player, count } => json!({ "type": "drew", "player": player, "count": count }),
+        GameEvent::Skipped { player } => json!({ "type": "skipped", "player": player }),
+        GameEvent::Won { player } => json!({ "type": "won", "player": player }),
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::card::{Card, CardColor};
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Palette {
+    /// The basic 16 ANSI colors every color terminal understands.
+    Ansi16,
+    TrueColor,
+    /// No escape codes at all.
+    Monochrome,
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "16" | "ansi" => Ok(Palette::Ansi16),
+            "truecolor" | "24bit" => Ok(Palette::TrueColor),
+            "mono" | "monochrome" | "none" => Ok(Palette::Monochrome),
+            _ => Err(format!("{s} is not a valid theme")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    /// Marks every color with a letter and suit glyph, so color is never the only way to tell cards apart.
+    pub colorblind: bool,
+}
+
+impl Theme {
+    /// Monochrome when `NO_COLOR` is set or output isn't a terminal, truecolor when `COLORTERM` advertises it.
+    pub fn detect() -> Theme {
+        let no_color = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+        let truecolor = std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit");
+
+        let palette = if no_color || !std::io::stdout().is_terminal() {
+            Palette::Monochrome
+        } else if truecolor {
+            Palette::TrueColor
+        } else {
+            Palette::Ansi16
+        };
+
+        Theme {
+            palette,
+            colorblind: false,
+        }
+    }
+
+    /// `text` in `color`, or in bold for cards that don't have one yet.
+    pub fn paint(&self, text: &str, color: Option<CardColor>) -> String {
+        let code = match (self.palette, color) {
+            (Palette::Monochrome, _) => return text.to_string(),
+            (_, None) => "1".to_string(),
+            (Palette::Ansi16, Some(CardColor::Red)) => "31".to_string(),
+            (Palette::Ansi16, Some(CardColor::Green)) => "32".to_string(),
+            (Palette::Ansi16, Some(CardColor::Yellow)) => "33".to_string(),
+            (Palette::Ansi16, Some(CardColor::Blue)) => "34".to_string(),
+            (Palette::TrueColor, Some(color)) => {
+                let (r, g, b) = rgb(color);
+                format!("38;2;{r};{g};{b}")
+            }
+        };
+
+        format!("\x1b[{code}m{text}\x1b[0m")
+    }
+
+    /// Fades `text` (which may already be painted) into the background.
+    pub fn dim(&self, text: &str) -> String {
+        match self.palette {
+            Palette::Monochrome => text.to_string(),
+            _ => format!("\x1b[2m{text}\x1b[0m"),
+        }
+    }
+
+    /// `text` split into four runs, one per card color, the way wild cards are printed.
+    fn rainbow(&self, text: &str) -> String {
+        let chars = text.chars().collect::<Vec<char>>();
+        let run = chars.len().div_ceil(4);
+
+        chars
+            .chunks(run.max(1))
+            .zip([CardColor::Red, CardColor::Blue, CardColor::Green, CardColor::Yellow])
+            .map(|(chunk, color)| self.paint(&chunk.iter().collect::<String>(), Some(color)))
+            .collect()
+    }
+
+    /// Whether colors need spelling out, because there are none or they can't be told apart.
+    pub fn needs_markers(&self) -> bool {
+        self.colorblind || self.palette == Palette::Monochrome
+    }
+
+    /// The letter and suit glyph standing in for `color` in colorblind mode.
+    pub fn marker(&self, color: CardColor) -> &'static str {
+        match color {
+            CardColor::Red => "♥R",
+            CardColor::Green => "♣G",
+            CardColor::Blue => "♠B",
+            CardColor::Yellow => "♦Y",
+        }
+    }
+
+    fn color_name(&self, color: CardColor) -> String {
+        let name = if self.colorblind { format!("{} {color}", self.marker(color)) } else { color.to_string() };
+
+        self.paint(&name, Some(color))
+    }
+
+    pub fn card_name(&self, card: Card) -> String {
+        match card {
+            Card::Wild { color } | Card::DrawFour { color } => {
+                let name = self.rainbow(if matches!(card, Card::Wild { .. }) { "Wild" } else { "Draw Four" });
+
+                match color {
+                    Some(color) => format!("{name} ({})", self.color_name(color)),
+                    None => name,
+                }
+            }
+            _ => {
+                let color = card.color().unwrap();
+                let name = if self.colorblind { format!("{} {card}", self.marker(color)) } else { card.to_string() };
+
+                self.paint(&name, Some(color))
+            }
+        }
+    }
+}
+
+pub fn rgb(color: CardColor) -> (u8, u8, u8) {
+    match color {
+        CardColor::Red => (215, 38, 0),
+        CardColor::Green => (55, 151, 17),
+        CardColor::Blue => (9, 86, 191),
+        CardColor::Yellow => (236, 212, 7),
+    }
+}
+
+/// Sets the theme for the rest of the process. Only the first call has any effect.
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn current() -> Theme {
+    *THEME.get_or_init(Theme::detect)
+}
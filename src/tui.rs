@@ -0,0 +1,392 @@
+//! Full-screen terminal front end. A [`TuiPlayer`] takes the human's turns, a [`TuiView`] follows the rest of the
+//! game, and both draw into the same [`Screen`].
+
+use std::io::{stdout, Write};
+use std::sync::{Arc, Mutex};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::card::{Card, CardColor};
+use crate::console::describe;
+use crate::game::{Direction, GameEvent, GameObserver, Table, Turn, TurnResult};
+use crate::player::Player;
+use crate::save;
+use crate::theme;
+use crate::theme::Palette;
+
+const COLORS: [CardColor; 4] = [CardColor::Red, CardColor::Green, CardColor::Blue, CardColor::Yellow];
+
+/// Switches the terminal to the full-screen UI until dropped.
+pub struct Session;
+
+impl Session {
+    pub fn start() -> std::io::Result<Session> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+
+        Ok(Session)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// What the human is being asked for, if anything.
+enum Prompt<'a> {
+    Waiting,
+    Card { turn: &'a Turn, selected: usize },
+    Color { selected: usize },
+}
+
+/// Everything the UI knows about the game, as seen from the human's seat.
+#[derive(Default)]
+pub struct Screen {
+    name: String,
+    /// Where the human sits; names can't be relied on, since an AI may go by the same one.
+    seat: Option<usize>,
+    players: Vec<String>,
+    hand_sizes: Vec<usize>,
+    current_player: usize,
+    clockwise: bool,
+    top_card: Option<Card>,
+    to_draw: u8,
+    hand: Vec<Card>,
+    log: Vec<String>,
+}
+
+impl Screen {
+    pub fn shared(name: &str) -> Arc<Mutex<Screen>> {
+        Arc::new(Mutex::new(Screen {
+            name: name.to_string(),
+            clockwise: true,
+            ..Screen::default()
+        }))
+    }
+
+    fn update(&mut self, table: &Table) {
+        self.players = table.names.iter().map(|n| n.to_string()).collect();
+        self.hand_sizes = table.hands.iter().map(|h| h.len()).collect();
+        self.current_player = table.current_player;
+        self.clockwise = table.direction == Direction::Clockwise;
+        self.top_card = table.top_card;
+        self.to_draw = table.to_draw;
+
+        if let Some(seat) = self.seat {
+            self.hand = table.hands[seat].to_vec();
+        }
+    }
+
+    fn render(&self, prompt: &Prompt) -> std::io::Result<()> {
+        let (width, height) = match size()? {
+            (0, _) | (_, 0) => (80, 24),
+            size => size,
+        };
+        let mut out = stdout();
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0), SetAttribute(Attribute::Bold), Print(format!("Uno - {}", self.name)), SetAttribute(Attribute::Reset))?;
+
+        // Table pane.
+        let arrow = if self.clockwise { "↓ clockwise" } else { "↑ counter-clockwise" };
+        queue!(out, MoveTo(0, 2), Print(format!("Direction: {arrow}")))?;
+
+        for (seat, (name, count)) in self.players.iter().zip(self.hand_sizes.iter()).enumerate() {
+            let marker = if seat == self.current_player { "▶" } else { " " };
+            queue!(out, MoveTo(2, 3 + seat as u16), Print(format!("{marker} {name:<12} {count:>2} card(s)")))?;
+        }
+
+        let table_bottom = 4 + self.players.len() as u16;
+        queue!(out, MoveTo(0, table_bottom), Print("Top card: "))?;
+
+        if let Some(card) = self.top_card {
+            print_card(&mut out, card, false, false)?;
+        }
+
+        if self.to_draw > 0 {
+            queue!(out, Print(format!("   Pending draw: +{}", self.to_draw)))?;
+        }
+
+        // Hand pane, wrapped to the terminal width.
+        let mut lines: Vec<Vec<(usize, Card)>> = vec![vec![]];
+        let mut line_width = 0;
+
+        for (i, card) in self.hand.iter().enumerate() {
+            let card_width = label(*card).chars().count() + 3;
+
+            if line_width + card_width > width as usize && line_width > 0 {
+                lines.push(vec![]);
+                line_width = 0;
+            }
+
+            line_width += card_width;
+            lines.last_mut().unwrap().push((i, *card));
+        }
+
+        let hand_top = height.saturating_sub(lines.len() as u16 + 3);
+
+        // Log pane, newest at the bottom.
+        let log_top = table_bottom + 2;
+        let log_rows = hand_top.saturating_sub(log_top + 1) as usize;
+
+        for (row, line) in self.log.iter().skip(self.log.len().saturating_sub(log_rows)).enumerate() {
+            let line = line.chars().take(width as usize).collect::<String>();
+            queue!(out, MoveTo(0, log_top + row as u16), Print(line))?;
+        }
+
+        queue!(out, MoveTo(0, hand_top), Print(format!("Your hand ({} card(s)):", self.hand.len())))?;
+
+        for (row, line) in lines.iter().enumerate() {
+            queue!(out, MoveTo(0, hand_top + 1 + row as u16))?;
+
+            for (i, card) in line {
+                let (playable, selected) = match prompt {
+                    Prompt::Card { turn, selected } => (turn.playable_hand.contains(card), *selected == *i),
+                    _ => (true, false),
+                };
+
+                print_card(&mut out, *card, selected, !playable)?;
+                queue!(out, Print(" "))?;
+            }
+        }
+
+        let help = match prompt {
+            Prompt::Waiting => "Waiting for the other players...".to_string(),
+            Prompt::Card { turn, .. } if turn.playable_hand.is_empty() => "No playable cards. Enter or D: draw   Q: quit".to_string(),
+            Prompt::Card { .. } => "←/→: choose   Enter: play   D: draw   Q: quit".to_string(),
+            Prompt::Color { .. } => "↑/↓: choose   Enter: confirm   Esc: back".to_string(),
+        };
+
+        queue!(out, MoveTo(0, height.saturating_sub(1)), SetAttribute(Attribute::Dim), Print(help), SetAttribute(Attribute::Reset))?;
+
+        if let Prompt::Color { selected } = prompt {
+            let left = width.saturating_sub(16) / 2;
+            let top = height.saturating_sub(8) / 2;
+
+            queue!(out, MoveTo(left, top), Print("┌──────────────┐"), MoveTo(left, top + 1), Print("│ Pick a color │"))?;
+
+            for (i, color) in COLORS.iter().enumerate() {
+                let marker = if i == *selected { "▶" } else { " " };
+
+                queue!(out, MoveTo(left, top + 2 + i as u16), Print(format!("│ {marker} ")))?;
+
+                if let Some(foreground) = terminal_color(*color) {
+                    queue!(out, SetForegroundColor(foreground))?;
+                }
+
+                queue!(out, Print(format!("{:<10}", color.to_string())), ResetColor, Print("│"))?;
+            }
+
+            queue!(out, MoveTo(left, top + 6), Print("└──────────────┘"))?;
+        }
+
+        out.flush()
+    }
+}
+
+/// Follows the game for the human's screen. Only the human's own hand is ever shown.
+pub struct TuiView(pub Arc<Mutex<Screen>>);
+
+impl GameObserver for TuiView {
+    fn observe(&mut self, event: &GameEvent, table: &Table) {
+        let mut screen = self.0.lock().unwrap();
+
+        if matches!(event, GameEvent::Started { .. }) {
+            screen.log.clear();
+        }
+
+        screen.update(table);
+
+        if matches!(event, GameEvent::TurnStarted { .. }) {
+            let _ = screen.render(&Prompt::Waiting);
+            return;
+        }
+
+        let line = match event {
+            GameEvent::Won { player } => format!("{} won!", table.names[*player]),
+            _ => describe(event, table, |c| c.to_string()),
+        };
+
+        screen.log.push(line);
+
+        let _ = screen.render(&Prompt::Waiting);
+    }
+}
+
+pub struct TuiPlayer {
+    name: String,
+    screen: Arc<Mutex<Screen>>,
+}
+
+impl TuiPlayer {
+    /// A player at `seat`, whose hand `screen` then shows.
+    pub fn new(name: String, seat: usize, screen: Arc<Mutex<Screen>>) -> TuiPlayer {
+        screen.lock().unwrap().seat = Some(seat);
+
+        TuiPlayer {
+            name,
+            screen,
+        }
+    }
+
+    fn choose_color(&self) -> Option<CardColor> {
+        let screen = self.screen.lock().unwrap();
+        let mut selected = 0;
+
+        loop {
+            let _ = screen.render(&Prompt::Color { selected });
+
+            match read_key()? {
+                KeyCode::Up | KeyCode::Char('k') => selected = (selected + COLORS.len() - 1) % COLORS.len(),
+                KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1) % COLORS.len(),
+                KeyCode::Char('r') => return Some(CardColor::Red),
+                KeyCode::Char('g') => return Some(CardColor::Green),
+                KeyCode::Char('b') => return Some(CardColor::Blue),
+                KeyCode::Char('y') => return Some(CardColor::Yellow),
+                KeyCode::Enter | KeyCode::Char(' ') => return Some(COLORS[selected]),
+                KeyCode::Esc => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Player for TuiPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn execute_turn(&mut self, turn: &Turn) -> TurnResult {
+        let playable = turn.full_hand
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| turn.playable_hand.contains(c))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        let mut choice = 0;
+
+        loop {
+            self.screen.lock().unwrap().hand = turn.full_hand.clone();
+
+            let selected = playable.get(choice).copied().unwrap_or(usize::MAX);
+            let _ = self.screen.lock().unwrap().render(&Prompt::Card { turn, selected });
+
+            let Some(key) = read_key() else { return TurnResult::Drew };
+
+            match key {
+                KeyCode::Left | KeyCode::Char('h') if !playable.is_empty() => choice = (choice + playable.len() - 1) % playable.len(),
+                KeyCode::Right | KeyCode::Char('l') if !playable.is_empty() => choice = (choice + 1) % playable.len(),
+                KeyCode::Char('d') => return TurnResult::Drew,
+                KeyCode::Enter | KeyCode::Char(' ') if playable.is_empty() => return TurnResult::Drew,
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    let mut card = turn.full_hand[playable[choice]];
+
+                    if !matches!(card, Card::Wild { .. } | Card::DrawFour { .. }) {
+                        return TurnResult::Played(card);
+                    }
+
+                    if let Some(color) = self.choose_color() {
+                        return TurnResult::Played(*card.with_color(color).unwrap());
+                    }
+                }
+                KeyCode::Char('q') => {
+                    drop(Session);
+                    std::process::exit(0);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn observe_turn(&self, _other: &dyn Player, _card: &Card) {
+        // Nothing to do here.
+    }
+
+    fn observe_turn_skip(&mut self, _observed_cards: Option<Vec<&Card>>) {
+        // The view picks up the new hand from the table.
+    }
+}
+
+/// Blocks until a key is pressed. Ctrl-C saves the game and quits.
+fn read_key() -> Option<KeyCode> {
+    loop {
+        match read().ok()? {
+            Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, .. }) => {
+                drop(Session);
+                save::autosave();
+                std::process::exit(130);
+            }
+            Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) => return Some(code),
+            _ => {}
+        }
+    }
+}
+
+/// The text on a card chip. When color can't be relied on, the color's letter and glyph go in front.
+fn label(card: Card) -> String {
+    let name = match card {
+        Card::Numeric { value, .. } => value.number().to_string(),
+        Card::Skip { .. } => "Skip".to_string(),
+        Card::Reverse { .. } => "Reverse".to_string(),
+        Card::DrawTwo { .. } => "+2".to_string(),
+        Card::Wild { .. } => "Wild".to_string(),
+        Card::DrawFour { .. } => "+4".to_string(),
+    };
+
+    let theme = theme::current();
+
+    match card.color() {
+        Some(color) if theme.needs_markers() => format!("{} {name}", theme.marker(color)),
+        _ => name,
+    }
+}
+
+fn terminal_color(color: CardColor) -> Option<Color> {
+    match theme::current().palette {
+        Palette::Monochrome => None,
+        Palette::TrueColor => {
+            let (r, g, b) = theme::rgb(color);
+            Some(Color::Rgb { r, g, b })
+        }
+        Palette::Ansi16 => Some(match color {
+            CardColor::Red => Color::Red,
+            CardColor::Green => Color::Green,
+            CardColor::Blue => Color::Blue,
+            CardColor::Yellow => Color::Yellow,
+        }),
+    }
+}
+
+fn print_card(out: &mut impl Write, card: Card, selected: bool, dim: bool) -> std::io::Result<()> {
+    if dim {
+        queue!(out, SetAttribute(Attribute::Dim))?;
+    }
+
+    if selected {
+        queue!(out, SetAttribute(Attribute::Reverse))?;
+    }
+
+    let background = match card.color() {
+        Some(color) => terminal_color(color),
+        None if theme::current().palette == Palette::Monochrome => None,
+        None => Some(Color::DarkGrey),
+    };
+
+    if let Some(background) = background {
+        queue!(out, SetBackgroundColor(background), SetForegroundColor(Color::Black))?;
+    }
+
+    queue!(
+        out,
+        Print(format!(" {} ", label(card))),
+        ResetColor,
+        SetAttribute(Attribute::Reset)
+    )
+}
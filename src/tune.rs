@@ -0,0 +1,258 @@
+//! Tunes an AI personality by evolution. A population of profiles plays seeded games against the built-in Hard AI;
+//! the ones that win most breed the next generation, and the best one at the end is written out as a personality.
+//!
+//! The budget is a number of games rather than a length of time, so a run is the same amount of work on any machine,
+//! and the same seed always gives the same result.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::GameState;
+use crate::personality::{AiProfile, CARD_KINDS};
+use crate::player::{AIDifficulty, Ai, Player};
+use crate::rules::RuleSet;
+
+const USAGE: &str = "\
+Usage: tune [options]
+  --budget <games>       games to play in all (default 8000)
+  --population <n>       profiles in each generation (default 20)
+  --games <n>            games each profile plays per generation (default 40)
+  --strategy <level>     easy, medium or hard: which way of picking a card to tune (default hard)
+  --seed <n>             seed for the games and the evolution (default 1)
+  --name <name>          what to call the personality (default tuned)
+  --out <file>           where to write it; otherwise it's printed";
+
+/// Five traits, then the five card weights.
+const GENES: usize = 10;
+/// How many of the best profiles go through to the next generation unchanged.
+const ELITE: usize = 2;
+/// Chance of each gene mutating, and how far it can move when it does.
+const MUTATION_RATE: f64 = 0.2;
+const MUTATION_SIZE: f64 = 0.2;
+const PLAYERS: usize = 4;
+
+type Genome = [f64; GENES];
+
+struct Settings {
+    budget: usize,
+    population: usize,
+    games: usize,
+    strategy: AIDifficulty,
+    seed: u64,
+    name: String,
+    out: Option<PathBuf>,
+}
+
+impl Settings {
+    fn parse(args: &[String]) -> Result<Settings, String> {
+        let mut settings = Settings {
+            budget: 8000,
+            population: 20,
+            games: 40,
+            strategy: AIDifficulty::Hard,
+            seed: 1,
+            name: "tuned".to_string(),
+            out: None,
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value.\n{USAGE}"));
+            let number = |value: &String| value.parse::<usize>().map_err(|_| format!("{value} is not a number."));
+
+            match arg.as_str() {
+                "--budget" => settings.budget = number(value()?)?,
+                "--population" => settings.population = number(value()?)?,
+                "--games" => settings.games = number(value()?)?,
+                "--strategy" => settings.strategy = value()?.parse()?,
+                "--seed" => settings.seed = number(value()?)? as u64,
+                "--name" => settings.name = value()?.clone(),
+                "--out" => settings.out = Some(PathBuf::from(value()?)),
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+
+        if settings.population < ELITE + 1 || settings.games == 0 {
+            return Err(format!("A generation needs at least {} profiles and one game.", ELITE + 1));
+        }
+
+        Ok(settings)
+    }
+}
+
+fn genome(profile: &AiProfile) -> Genome {
+    let mut genome = [0.0; GENES];
+    genome[..5].copy_from_slice(&[profile.aggression, profile.stacking, profile.color_hoarding, profile.bluff_draw, profile.wild_saving]);
+    genome[5..].copy_from_slice(&profile.weights);
+    genome
+}
+
+fn profile(genome: &Genome, settings: &Settings) -> AiProfile {
+    let mut weights = [0.0; CARD_KINDS.len()];
+    weights.copy_from_slice(&genome[5..]);
+
+    // The weights only matter relative to each other, but they can't all be nothing.
+    if weights.iter().all(|w| *w == 0.0) {
+        weights = [1.0; CARD_KINDS.len()];
+    }
+
+    AiProfile {
+        personality: settings.name.clone(),
+        names: vec![],
+        strategy: settings.strategy,
+        aggression: genome[0],
+        stacking: genome[1],
+        color_hoarding: genome[2],
+        bluff_draw: genome[3],
+        wild_saving: genome[4],
+        weights,
+    }
+}
+
+/// Plays one game with `candidate` in `seat` and Hard AIs everywhere else, and says whether the candidate won.
+fn play(candidate: &AiProfile, seed: u64, seat: usize) -> bool {
+    let players = (0..PLAYERS)
+        .map(|i| {
+            let profile = if i == seat { candidate.clone() } else { AiProfile::for_difficulty(AIDifficulty::Hard) };
+            let mut ai = Ai::with_profile(StdRng::seed_from_u64(seed.wrapping_add(i as u64 + 1)), profile);
+
+            // Names are how the winner is reported, so they have to be told apart.
+            ai.set_name(&i.to_string());
+            Box::new(ai) as Box<dyn Player>
+        })
+        .collect();
+
+    let mut game = GameState::new(players, RuleSet::default());
+    game.set_seed(seed);
+    game.set_turn_delay(Duration::ZERO);
+
+    game.start() == Some(seat.to_string())
+}
+
+/// The share of `seeds` games the genome wins, moving round the table so every seat gets its turn.
+fn fitness(genome: &Genome, settings: &Settings, seeds: &[u64]) -> f64 {
+    let candidate = profile(genome, settings);
+    let wins = seeds.iter().enumerate().filter(|(game, seed)| play(&candidate, **seed, game % PLAYERS)).count();
+
+    wins as f64 / seeds.len() as f64
+}
+
+/// Scores the whole population, spread over every core. Each game is seeded, so the scores don't depend on how the
+/// work happens to be split up.
+fn evaluate(population: &[Genome], settings: &Settings, seeds: &[u64]) -> Vec<f64> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = population.len().div_ceil(threads);
+
+    std::thread::scope(|scope| {
+        let handles = population
+            .chunks(chunk)
+            .map(|genomes| scope.spawn(move || genomes.iter().map(|g| fitness(g, settings, seeds)).collect::<Vec<f64>>()))
+            .collect::<Vec<_>>();
+
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+/// The better of three picked at random.
+fn select<'a>(population: &'a [Genome], scores: &[f64], rng: &mut StdRng) -> &'a Genome {
+    let best = (0..3).map(|_| rng.gen_range(0..population.len())).max_by(|a, b| scores[*a].total_cmp(&scores[*b])).unwrap();
+    &population[best]
+}
+
+fn breed(a: &Genome, b: &Genome, rng: &mut StdRng) -> Genome {
+    let mut child = [0.0; GENES];
+
+    for (gene, (a, b)) in child.iter_mut().zip(a.iter().zip(b)) {
+        *gene = if rng.gen_bool(0.5) { *a } else { *b };
+
+        if rng.gen_bool(MUTATION_RATE) {
+            *gene = (*gene + rng.gen_range(-MUTATION_SIZE..=MUTATION_SIZE)).clamp(0.0, 1.0);
+        }
+    }
+
+    child
+}
+
+pub fn run(args: &[String]) {
+    let settings = match Settings::parse(args) {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    let per_generation = settings.population * settings.games;
+    let generations = settings.budget / per_generation;
+
+    if generations == 0 {
+        println!("A budget of {} games doesn't cover one generation, which takes {per_generation}.", settings.budget);
+        return;
+    }
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    // Start from the strategy's own difficulty, so there's always something at least as good as that to fall back on.
+    let mut population = vec![genome(&AiProfile::for_difficulty(settings.strategy))];
+    population.extend((1..settings.population).map(|_| [(); GENES].map(|_| rng.gen_range(0.0..=1.0))));
+
+    println!(
+        "Tuning a {} AI against Hard AIs: {generations} generations of {} profiles, {} games each.",
+        settings.strategy, settings.population, settings.games
+    );
+
+    let mut best = (0.0, population[0]);
+
+    for generation in 0..generations {
+        // Everyone in a generation plays the same deals, so they're compared fairly; each generation gets new ones.
+        let seeds = (0..settings.games as u64)
+            .map(|game| settings.seed.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(((generation as u64) << 32) + game))
+            .collect::<Vec<u64>>();
+        let scores = evaluate(&population, &settings, &seeds);
+
+        let mut ranked = (0..population.len()).collect::<Vec<usize>>();
+        ranked.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+
+        best = (scores[ranked[0]], population[ranked[0]]);
+
+        println!(
+            "Generation {}: the best won {:.0}% of its games, the average {:.0}%.",
+            generation + 1,
+            best.0 * 100.0,
+            scores.iter().sum::<f64>() / scores.len() as f64 * 100.0
+        );
+
+        let mut next = ranked.iter().take(ELITE).map(|i| population[*i]).collect::<Vec<Genome>>();
+
+        while next.len() < population.len() {
+            let child = breed(select(&population, &scores, &mut rng), select(&population, &scores, &mut rng), &mut rng);
+            next.push(child);
+        }
+
+        population = next;
+    }
+
+    let tuned = profile(&best.1, &settings);
+    let toml = format!(
+        "# Tuned with seed {}: won {:.0}% of its last {} games against three Hard AIs.\n{}",
+        settings.seed,
+        best.0 * 100.0,
+        settings.games,
+        tuned.to_toml()
+    );
+
+    match &settings.out {
+        Some(path) => match std::fs::write(path, toml) {
+            Ok(()) => {
+                let (name, path) = (&settings.name, path.display());
+                println!("Wrote the {name} personality to {path}. Play against it with --personalities {path} --personality {name}.");
+            }
+            Err(e) => println!("Could not write {}: {e}", path.display()),
+        },
+        None => print!("{toml}"),
+    }
+}
@@ -0,0 +1,225 @@
+//! Runs the real server on a free port and talks to it over WebSockets, the way the web page does.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+/// The server process, stopped when the test is done with it.
+struct Server {
+    child: Child,
+    address: String,
+    data: PathBuf,
+}
+
+impl Server {
+    fn start(name: &str) -> Server {
+        // Keep the profiles and match history the server writes away from the real ones.
+        let data = std::env::temp_dir().join(format!("uno_cli-test-{name}-{}", std::process::id()));
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_uno_cli"))
+            .args(["--serve", "127.0.0.1:0"])
+            .env("XDG_DATA_HOME", &data)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("the server starts");
+
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+
+        // Games print as they go; keep reading so the server never writes to a closed pipe.
+        std::thread::spawn(move || std::io::copy(&mut stdout, &mut std::io::sink()));
+
+        let address = line.trim().strip_prefix("Serving Uno on http://").unwrap_or_else(|| panic!("unexpected banner: {line}")).to_string();
+
+        Server { child, address, data }
+    }
+
+    fn connect(&self) -> Client {
+        let stream = TcpStream::connect(&self.address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+
+        let (socket, _) = tungstenite::client(format!("ws://{}/ws", self.address), stream).unwrap();
+        Client(socket)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.data);
+    }
+}
+
+struct Client(WebSocket<TcpStream>);
+
+impl Client {
+    fn send(&mut self, message: Value) {
+        self.0.send(Message::text(message.to_string())).unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        loop {
+            match self.0.read().unwrap() {
+                Message::Text(text) => return serde_json::from_str(text.as_str()).unwrap(),
+                Message::Close(_) => panic!("the server closed the connection"),
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips ahead to the next message of `kind`.
+    fn receive_until(&mut self, kind: &str) -> Value {
+        loop {
+            let message = self.receive();
+
+            if message["type"] == kind {
+                return message;
+            }
+        }
+    }
+}
+
+/// Says hello as `name`, and starts a game against one AI in a room of its own.
+fn start_alone(client: &mut Client, name: &str) {
+    client.send(json!({ "type": "hello", "name": name }));
+    client.receive_until("rooms");
+    client.send(json!({ "type": "create", "name": "Table", "ai_players": 1 }));
+    client.receive_until("room");
+    client.send(json!({ "type": "ready" }));
+    client.receive_until("room");
+    client.send(json!({ "type": "start" }));
+    client.receive_until("started");
+}
+
+/// Asks for the room list until `done` is happy with it, or fails after a few seconds.
+fn wait_for_rooms(client: &mut Client, done: impl Fn(&[Value]) -> bool) {
+    let give_up = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        client.send(json!({ "type": "list" }));
+        let rooms = client.receive_until("rooms");
+
+        if done(rooms["rooms"].as_array().unwrap()) {
+            return;
+        }
+
+        assert!(Instant::now() < give_up, "still listed: {rooms}");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn serves_the_page() {
+    let server = Server::start("page");
+    let mut stream = TcpStream::connect(&server.address).unwrap();
+
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(response.contains("<html"), "{response}");
+}
+
+#[test]
+fn players_join_a_room_and_start_a_game() {
+    let server = Server::start("room");
+
+    let mut host = server.connect();
+    host.send(json!({ "type": "create", "name": "Table" }));
+    assert_eq!(host.receive(), json!({ "type": "error", "message": "Say hello with your name first." }));
+
+    host.send(json!({ "type": "hello", "name": "Ada" }));
+    assert_eq!(host.receive(), json!({ "type": "rooms", "rooms": [] }));
+
+    host.send(json!({ "type": "create", "name": "Table", "ai_players": 1, "difficulty": "easy" }));
+    let room = host.receive();
+    assert_eq!(room["type"], "room");
+    assert_eq!(room["is_host"], true);
+    assert_eq!(room["room"]["name"], "Table");
+    assert_eq!(room["room"]["ai_players"], 1);
+    assert_eq!(room["room"]["difficulty"], "Easy");
+    assert_eq!(room["room"]["members"], json!([{ "name": "Ada", "ready": false, "rating": 1500.0 }]));
+
+    let mut guest = server.connect();
+    guest.send(json!({ "type": "hello", "name": "Grace" }));
+    let rooms = guest.receive();
+    assert_eq!(rooms["rooms"].as_array().unwrap().len(), 1);
+    assert_eq!(rooms["rooms"][0]["name"], "Table");
+
+    guest.send(json!({ "type": "join", "room": rooms["rooms"][0]["id"] }));
+
+    for (client, is_host) in [(&mut host, true), (&mut guest, false)] {
+        let room = client.receive();
+        assert_eq!(room["is_host"], is_host);
+        assert_eq!(room["room"]["members"].as_array().unwrap().len(), 2);
+    }
+
+    guest.send(json!({ "type": "start" }));
+    assert_eq!(guest.receive(), json!({ "type": "error", "message": "Only the host can start the game." }));
+
+    host.send(json!({ "type": "ready" }));
+    host.receive();
+    guest.receive();
+    host.send(json!({ "type": "start" }));
+    assert_eq!(host.receive(), json!({ "type": "error", "message": "Everyone has to be ready first." }));
+
+    guest.send(json!({ "type": "ready" }));
+    host.receive();
+    guest.receive();
+    host.send(json!({ "type": "start" }));
+
+    for client in [&mut host, &mut guest] {
+        let started = client.receive_until("started");
+        let players = started["players"].as_array().unwrap();
+
+        assert_eq!(players.len(), 3);
+        assert_eq!(players[..2], [json!("Ada"), json!("Grace")]);
+    }
+
+    // The seat after the first moves first.
+    let turn = guest.receive_until("turn");
+    assert_eq!(turn["hand"].as_array().unwrap().len(), 7);
+    assert!(turn["playable"].is_array());
+}
+
+#[test]
+fn leaving_the_last_seat_stops_the_game() {
+    let server = Server::start("leave");
+
+    let mut player = server.connect();
+    start_alone(&mut player, "Ada");
+
+    let mut lobby = server.connect();
+    lobby.send(json!({ "type": "hello", "name": "Grace" }));
+    assert_eq!(lobby.receive_until("rooms")["rooms"][0]["playing"], true);
+
+    // Leaving works mid-game, and takes the player back to the lobby.
+    player.send(json!({ "type": "leave" }));
+    player.receive_until("rooms");
+
+    wait_for_rooms(&mut lobby, |rooms| rooms.is_empty());
+}
+
+#[test]
+fn a_game_everyone_disconnects_from_is_stopped() {
+    let server = Server::start("disconnect");
+
+    let mut player = server.connect();
+    start_alone(&mut player, "Ada");
+    drop(player);
+
+    let mut lobby = server.connect();
+    lobby.send(json!({ "type": "hello", "name": "Grace" }));
+    lobby.receive_until("rooms");
+
+    wait_for_rooms(&mut lobby, |rooms| rooms.is_empty());
+}